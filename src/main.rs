@@ -9,20 +9,28 @@ mod mcp;
 mod utils;
 use crate::mcp::mcp_aliyun_cli::RunAliyunCliCommand;
 use crate::mcp::mcp_aliyun_log_cli::RunAliyunLogCliCommand;
-use crate::mcp::mcp_config::{GetConfig, GetNacosConfig, GetSlsConfig};
+use crate::mcp::mcp_config::{GetConfig, GetConfigStatus, GetNacosConfig, GetSlsConfig};
 use crate::mcp::mcp_mysql::{
     ExecuteMysqlQuery, ListMysqlConnections, ListMysqlDatabases, ListMysqlTables,
 };
-use crate::mcp::mcp_nacos::{GetNacosConfigByClient, GetNacosServiceInfoByClient};
+use crate::mcp::mcp_nacos::{
+    DiffNacosConfig, GetNacosConfigByClient, GetNacosConfigHistory, GetNacosConfigRevision,
+    GetNacosServiceInfoByClient, GetNacosServiceInstances, ListNacosClusters,
+    ListNacosNamespaces, ListNacosServiceSubscribers, ListNacosServices,
+    ListUnhealthyNacosServices, PublishNacosConfig, SearchNacosConfigs,
+};
+use crate::mcp::mcp_sls::{QuerySlsLogs, SlsGetHistograms, SlsGetLogs, SlsListLogstores};
 use crate::mcp::mcp_redis::{ExecuteRedisCommand, ListRedisConnections, ListRedisDatabases};
 
 use crate::mcp::mcp_elasticsearch::{
-    EsGetAliases, EsGetHealth, EsGetIndex, EsGetMapping, EsGetVersion, EsIndexExists,
-    EsListConfigs, EsSearch,
+    EsAggregate, EsGetAliases, EsGetHealth, EsGetIndex, EsGetMapping, EsGetVersion,
+    EsIndexExists, EsListConfigs, EsSearch, EsSearchPage,
+};
+use crate::mcp::mcp_elasticsearch_fields::{
+    GenerateEsFieldsCatalog, GetEsFieldsConfig, GetEsIndexFields, ValidateEsQuery,
 };
-use crate::mcp::mcp_elasticsearch_fields::{GetEsFieldsConfig, GetEsIndexFields};
 
-use crate::mcp::mcp_time::GetCurrentTime;
+use crate::mcp::mcp_time::{ConvertTime, GetCurrentTime, ResolveTimeRange};
 use crate::utils::nacos_config::init_nacos_config;
 
 #[tokio::main]
@@ -43,8 +51,15 @@ async fn main() -> Result<(), anyhow::Error> {
             RunAliyunLogCliCommand::tool(),
             RunAliyunLogCliCommand::call(),
         )
+        .register_tool(QuerySlsLogs::tool(), QuerySlsLogs::call())
+        .register_tool(SlsGetLogs::tool(), SlsGetLogs::call())
+        .register_tool(SlsGetHistograms::tool(), SlsGetHistograms::call())
+        .register_tool(SlsListLogstores::tool(), SlsListLogstores::call())
         .register_tool(GetCurrentTime::tool(), GetCurrentTime::call())
+        .register_tool(ConvertTime::tool(), ConvertTime::call())
+        .register_tool(ResolveTimeRange::tool(), ResolveTimeRange::call())
         .register_tool(GetConfig::tool(), GetConfig::call())
+        .register_tool(GetConfigStatus::tool(), GetConfigStatus::call())
         .register_tool(
             GetNacosConfigByClient::tool(),
             GetNacosConfigByClient::call(),
@@ -53,6 +68,23 @@ async fn main() -> Result<(), anyhow::Error> {
             GetNacosServiceInfoByClient::tool(),
             GetNacosServiceInfoByClient::call(),
         )
+        .register_tool(ListNacosClusters::tool(), ListNacosClusters::call())
+        .register_tool(ListNacosNamespaces::tool(), ListNacosNamespaces::call())
+        .register_tool(SearchNacosConfigs::tool(), SearchNacosConfigs::call())
+        .register_tool(GetNacosConfigHistory::tool(), GetNacosConfigHistory::call())
+        .register_tool(GetNacosConfigRevision::tool(), GetNacosConfigRevision::call())
+        .register_tool(DiffNacosConfig::tool(), DiffNacosConfig::call())
+        .register_tool(ListNacosServices::tool(), ListNacosServices::call())
+        .register_tool(GetNacosServiceInstances::tool(), GetNacosServiceInstances::call())
+        .register_tool(
+            ListUnhealthyNacosServices::tool(),
+            ListUnhealthyNacosServices::call(),
+        )
+        .register_tool(
+            ListNacosServiceSubscribers::tool(),
+            ListNacosServiceSubscribers::call(),
+        )
+        .register_tool(PublishNacosConfig::tool(), PublishNacosConfig::call())
         .register_tool(GetSlsConfig::tool(), GetSlsConfig::call())
         .register_tool(GetNacosConfig::tool(), GetNacosConfig::call())
         .register_tool(ExecuteMysqlQuery::tool(), ExecuteMysqlQuery::call())
@@ -69,9 +101,13 @@ async fn main() -> Result<(), anyhow::Error> {
         .register_tool(EsGetMapping::tool(), EsGetMapping::call())
         .register_tool(EsGetHealth::tool(), EsGetHealth::call())
         .register_tool(EsSearch::tool(), EsSearch::call())
+        .register_tool(EsSearchPage::tool(), EsSearchPage::call())
+        .register_tool(EsAggregate::tool(), EsAggregate::call())
         .register_tool(EsGetVersion::tool(), EsGetVersion::call())
         .register_tool(GetEsFieldsConfig::tool(), GetEsFieldsConfig::call())
         .register_tool(GetEsIndexFields::tool(), GetEsIndexFields::call())
+        .register_tool(ValidateEsQuery::tool(), ValidateEsQuery::call())
+        .register_tool(GenerateEsFieldsCatalog::tool(), GenerateEsFieldsCatalog::call())
         .build();
 
     let mcp_server_transport =
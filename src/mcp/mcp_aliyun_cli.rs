@@ -2,28 +2,41 @@ use anyhow::Result;
 use mcp_core::tool_text_content;
 use mcp_core::types::ToolResponseContent;
 use mcp_core_macros::tool;
+use crate::utils::aliyun_cli::{
+    parse_aliyun_cli_command, parse_json_body, take_output_arg, AliyunCliError, OutputProjection,
+};
+use crate::utils::cli_runner::run_cli;
 
 // https://next.api.aliyun.com/api/Sls/2020-12-30/CreateProject?spm=a2c4g.11186623.0.0.23417a4fwfdbTI&lang=JAVA&sdkStyle=dara&params=%7B%7D&RegionId=cn-beijing&tab=CLI
 // https://help.aliyun.com/zh/sls/developer-reference/api-sls-2020-12-30-listlogstores?spm=a2c4g.11186623.help-menu-28958.d_3_2_3_1_10.22b35b8cXg6GRV
 #[tool(
     name = "RunAliyunCliCommand",
-    description = "Execute any aliyun CLI command and return results. Example: {\"command\":\"sls GetLogs --ProjectName ack-test-cluster-log --LogstoreName user-center-api --from 1718000000 --to 1718003600\"}",
-    params(command = "The aliyun CLI command string to execute (without aliyun prefix)")
+    description = "Execute an aliyun CLI command and return the parsed JSON result. Only product/API pairs allowed by ALIYUN_CLI_ALLOWED_APIS can be called (read-only Describe*/List*/Get* by default). Arguments are split with shell quoting rules, so quote values containing spaces or JSON. API failures are returned as errors with the Aliyun error code and RequestId. Example: {\"command\":\"ecs DescribeInstances --RegionId cn-beijing\",\"output\":\"cols=InstanceId,Status rows=Instances.Instance[]\"}",
+    params(
+        command = "The aliyun CLI command string to execute (without aliyun prefix)",
+        output = "Optional projection like the aliyun CLI --output option: 'cols=InstanceId,Status rows=Instances.Instance[]', returns a list of objects with only those columns"
+    )
 )]
-pub async fn run_aliyun_cli_command(command: String) -> Result<ToolResponseContent> {
-    use std::process::Command as SysCommand;
-    let args: Vec<&str> = command.split_whitespace().collect();
-    let cli_output = SysCommand::new("aliyun").args(&args).output();
-    let cli_result = match cli_output {
-        Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            format!(
-                "[aliyun cli stdout]:\n{}\n[aliyun cli stderr]:\n{}",
-                stdout, stderr
-            )
-        }
-        Err(e) => format!("[aliyun cli error]: {}", e),
+pub async fn run_aliyun_cli_command(command: String, output: Option<String>) -> Result<ToolResponseContent> {
+    let mut args = parse_aliyun_cli_command(&command)?;
+    // 命令中自带的 --output 与参数 output 等价，统一由工具做投影
+    let projection = match output.or_else(|| take_output_arg(&mut args)) {
+        Some(spec) if !spec.trim().is_empty() => Some(OutputProjection::parse(&spec)?),
+        _ => None,
     };
-    Ok(tool_text_content!(cli_result))
+
+    let cli_output = run_cli("aliyun", &args).await?;
+    if !cli_output.success() {
+        return Err(AliyunCliError::from_output(&cli_output).into());
+    }
+
+    let Some(body) = parse_json_body(&cli_output.stdout) else {
+        // 非 JSON 输出（如帮助信息）原样返回
+        return Ok(tool_text_content!(cli_output.stdout));
+    };
+    let result = match projection {
+        Some(projection) => projection.apply(&body)?,
+        None => body,
+    };
+    Ok(tool_text_content!(serde_json::to_string_pretty(&result)?))
 }
@@ -4,26 +4,23 @@ use anyhow::Result;
 use mcp_core::tool_text_content;
 use mcp_core::types::ToolResponseContent;
 use mcp_core_macros::tool;
+use crate::utils::aliyun_cli::{parse_aliyun_log_cli_command, parse_json_body, AliyunCliError};
+use crate::utils::cli_runner::run_cli;
 
 #[tool(
     name = "RunAliyunLogCliCommand",
-    description = "Execute aliyunlog CLI log subcommand and return results. If unsure about what to execute, first call the GetConfig MCP tool to get configuration information. If time is involved, first call GetCurrentTime to get the current time. Example: {\"command\":\"log get_log_all --project=xxx --logstore=xxx --from_time=2024-06-01 --to_time=2024-06-02\"}",
+    description = "Execute a read-only aliyunlog CLI log subcommand (allowed by ALIYUN_LOG_CLI_ALLOWED_COMMANDS, default get_* and list_*) and return the result, parsed as JSON when possible; failures are returned as errors with the error code and RequestId. If unsure about what to execute, first call the GetConfig MCP tool to get configuration information. If time is involved, first call GetCurrentTime to get the current time. Example: {\"command\":\"log get_log_all --project=xxx --logstore=xxx --from_time=2024-06-01 --to_time=2024-06-02\"}",
     params(command = "The aliyunlog CLI log subcommand string to execute (without aliyunlog prefix)")
 )]
 pub async fn run_aliyun_log_cli_command(command: String) -> Result<ToolResponseContent> {
-    use std::process::Command as SysCommand;
-    let args: Vec<&str> = command.split_whitespace().collect();
-    let cli_output = SysCommand::new("aliyunlog").args(&args).output();
-    let cli_result = match cli_output {
-        Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            format!(
-                "[aliyunlog cli stdout]:\n{}\n[aliyunlog cli stderr]:\n{}",
-                stdout, stderr
-            )
-        }
-        Err(e) => format!("[aliyunlog cli error]: {}", e),
-    };
-    Ok(tool_text_content!(cli_result))
+    let args = parse_aliyun_log_cli_command(&command)?;
+    let output = run_cli("aliyunlog", &args).await?;
+    if !output.success() {
+        return Err(AliyunCliError::from_output(&output).into());
+    }
+    // aliyunlog 输出 JSON 时格式化返回，否则原样返回
+    match parse_json_body(&output.stdout) {
+        Some(body) => Ok(tool_text_content!(serde_json::to_string_pretty(&body)?)),
+        None => Ok(tool_text_content!(output.stdout)),
+    }
 }
@@ -1,4 +1,5 @@
 // Core functionality for retrieving and managing configuration information
+use crate::utils::config_snapshot::get_config_status as get_status;
 use crate::utils::nacos_config::get_config_inner;
 use anyhow::Result;
 use mcp_core::tool_text_content;
@@ -34,3 +35,13 @@ pub async fn get_nacos_config() -> Result<ToolResponseContent> {
     let nacos_config = serde_yaml::to_string(&config.nacos).unwrap();
     Ok(tool_text_content!(nacos_config))
 }
+
+#[tool(
+    name = "GetConfigStatus",
+    description = "Get the configuration source status: whether the server is running on live Nacos config, a cached local snapshot (Nacos unreachable), or a local file (offline mode), and since when"
+)]
+pub async fn get_config_status() -> Result<ToolResponseContent> {
+    let status = get_status();
+    let yaml = serde_yaml::to_string(&status)?;
+    Ok(tool_text_content!(yaml))
+}
@@ -1,228 +1,583 @@
 use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64_STANDARD, Engine};
 use elasticsearch::{
-    auth::Credentials,
-    http::transport::{SingleNodeConnectionPool, TransportBuilder},
-    indices::{IndicesExistsParts, IndicesGetAliasParts, IndicesGetMappingParts},
+    auth::{ClientCertificate, Credentials},
+    cert::{Certificate, CertificateValidation},
+    http::headers::{HeaderMap, HeaderValue, AUTHORIZATION},
+    http::request::JsonBody,
+    http::transport::{SingleNodeConnectionPool, Transport, TransportBuilder},
+    http::Method,
     Elasticsearch as ES8,
 };
-use serde_json::Value;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::env;
+use std::future::Future;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
 use mcp_core::tool_text_content;
 use mcp_core::types::ToolResponseContent;
 use mcp_core_macros::tool;
 use crate::utils::config::{AppConfig, ElasticsearchConfig};
+use crate::utils::date_util::{resolve_time_range, resolve_time_zone};
+use crate::utils::es_aggregation::{
+    build_aggregation_query, flatten_aggregation_response, AggMetric, AggregationRequest,
+};
+use crate::utils::es_error::EsError;
+use crate::utils::es_fields_catalog::{
+    check_aggregatable_fields, find_catalog_index, validate_search_query, QueryValidationMode,
+};
+use crate::utils::es_pagination::{
+    apply_default_sort, build_page, keep_alive_param, new_page_token, save_session,
+    take_expired_sessions, take_session, PageCursor, PageResult, PageSession,
+};
+use crate::utils::es_search::{
+    compact_search_response, es_max_response_bytes, es_max_search_size, prepare_search_query,
+    render_with_budget,
+};
+use crate::utils::es_node_pool::{parse_sniffed_nodes, EsNodePool, EsNodeStatus};
+use crate::utils::es_version::{normalize_mappings, normalize_search_response, EsDistribution, EsVersion};
 
-pub enum MCPElasticsearch {
-    V6(reqwest::Client, ElasticsearchConfig),
-    V8(ES8, ElasticsearchConfig),
+/// 429 / 503 重试的初始等待时间，之后指数增长
+const ES_RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+/// 单次重试的最长等待时间，Retry-After 也不超过该值
+const ES_RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
+
+pub fn es_request_timeout_secs() -> u64 {
+    env::var("ES_REQUEST_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(30)
 }
 
-impl MCPElasticsearch {
-    pub fn new(config: &ElasticsearchConfig) -> Result<Self> {
-        match config.version.as_str() {
-            "6.8" => {
-                let mut client_builder = reqwest::Client::builder();
-
-                if let (Some(username), Some(password)) = (&config.username, &config.password) {
-                    let auth = base64::encode(format!("{}:{}", username, password));
-                    client_builder = client_builder.default_headers({
-                        let mut headers = reqwest::header::HeaderMap::new();
-                        headers.insert(
-                            reqwest::header::AUTHORIZATION,
-                            format!("Basic {}", auth).parse().unwrap(),
-                        );
-                        headers
-                    });
-                }
+pub fn es_max_retries() -> u32 {
+    env::var("ES_MAX_RETRIES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(3)
+}
 
-                let client = client_builder.build()?;
-                Ok(Self::V6(client, config.clone()))
-            }
-            "8.0" => {
-                let url = url::Url::parse(&config.url)?;
-                let conn_pool = SingleNodeConnectionPool::new(url);
-                let mut transport_builder = TransportBuilder::new(conn_pool);
-
-                if let (Some(username), Some(password)) = (&config.username, &config.password) {
-                    transport_builder = transport_builder.auth(Credentials::Basic(
-                        username.clone(),
-                        password.clone(),
-                    ));
-                }
+// 按配置名缓存客户端，配置变化（热更新）后重建
+static ES_CLIENTS: Lazy<Mutex<HashMap<String, Arc<MCPElasticsearch>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
 
-                let transport = transport_builder.build()?;
-                let client = ES8::new(transport);
-                Ok(Self::V8(client, config.clone()))
+/// 获取缓存的客户端，配置不存在或已变化时移除 / 重建缓存
+pub async fn get_es_client(config_name: &str) -> Result<Arc<MCPElasticsearch>> {
+    let config = crate::utils::nacos_config::get_config_inner()?;
+    let es_config = get_es_config(&config, config_name)
+        .ok_or_else(|| anyhow::anyhow!("Configuration not found: {}", config_name))?
+        .clone();
+    {
+        let mut clients = ES_CLIENTS.lock().unwrap();
+        clients.retain(|name, _| get_es_config(&config, name).is_some());
+        if let Some(client) = clients.get(config_name) {
+            if client.config() == &es_config {
+                return Ok(client.clone());
             }
-            _ => Err(anyhow!("Unsupported Elasticsearch version: {}", config.version)),
+            println!("[Elasticsearch] 配置 {} 已变化，重建客户端", config_name);
         }
     }
+    // 版本识别需要请求集群，构建期间不持有锁
+    let client = Arc::new(MCPElasticsearch::new(&es_config).await?);
+    ES_CLIENTS
+        .lock()
+        .unwrap()
+        .insert(config_name.to_string(), client.clone());
+    Ok(client)
+}
 
-    pub async fn index_exists(&self, index: &str) -> Result<bool> {
-        match self {
-            Self::V6(client, _) => {
-                let response = client
-                    .head(format!("{}/{}", self.get_base_url(), index))
-                    .send()
-                    .await?;
-                Ok(response.status().is_success())
-            }
-            Self::V8(client, _) => {
-                let response = client
-                    .indices()
-                    .exists(IndicesExistsParts::Index(&[index]))
-                    .send()
-                    .await?;
-                Ok(response.status_code().is_success())
-            }
+/// 用于重试判断的 HTTP 状态码与 Retry-After，兼容 reqwest 与 elasticsearch 两种响应
+trait HttpStatus {
+    fn status_u16(&self) -> u16;
+    fn retry_after(&self) -> Option<&str>;
+}
+
+impl HttpStatus for reqwest::Response {
+    fn status_u16(&self) -> u16 {
+        self.status().as_u16()
+    }
+
+    fn retry_after(&self) -> Option<&str> {
+        self.headers().get("retry-after").and_then(|v| v.to_str().ok())
+    }
+}
+
+impl HttpStatus for elasticsearch::http::response::Response {
+    fn status_u16(&self) -> u16 {
+        self.status_code().as_u16()
+    }
+
+    fn retry_after(&self) -> Option<&str> {
+        self.headers().get("retry-after").and_then(|v| v.to_str().ok())
+    }
+}
+
+/// 解析 Retry-After：秒数或 HTTP 日期
+fn parse_retry_after(value: &str, now: chrono::DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some((at.with_timezone(&Utc) - now).to_std().unwrap_or(Duration::ZERO))
+}
+
+/// 第 attempt 次重试的等待时间：优先 Retry-After，否则指数退避，均不超过 ES_RETRY_MAX_DELAY
+fn retry_delay(attempt: u32, retry_after: Option<&str>) -> Duration {
+    retry_after
+        .and_then(|value| parse_retry_after(value, Utc::now()))
+        .unwrap_or_else(|| ES_RETRY_BASE_DELAY.saturating_mul(2u32.saturating_pow(attempt)))
+        .min(ES_RETRY_MAX_DELAY)
+}
+
+/// 发送请求，遇到 429（限流）或 503（不可用）时按 Retry-After 或指数退避重试
+async fn send_with_retry<F, Fut, R, E>(send: F) -> Result<R>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = std::result::Result<R, E>>,
+    R: HttpStatus,
+    E: Into<anyhow::Error>,
+{
+    let max_retries = es_max_retries();
+    let mut attempt = 0;
+    loop {
+        let response = send().await.map_err(Into::into)?;
+        let status = response.status_u16();
+        if (status == 429 || status == 503) && attempt < max_retries {
+            let delay = retry_delay(attempt, response.retry_after());
+            attempt += 1;
+            println!(
+                "[Elasticsearch] 请求返回 {}，{}ms 后第 {} 次重试",
+                status,
+                delay.as_millis(),
+                attempt
+            );
+            tokio::time::sleep(delay).await;
+            continue;
         }
+        return Ok(response);
     }
+}
 
-    pub async fn get_index(&self, index: &str) -> Result<Value> {
-        match self {
-            Self::V6(client, _) => {
-                let response = client
-                    .get(format!("{}/{}", self.get_base_url(), index))
-                    .send()
-                    .await?;
-                let body = response.json::<Value>().await?;
-                Ok(body)
-            }
-            Self::V8(client, _) => {
-                let response = client
-                    .indices()
-                    .get(elasticsearch::indices::IndicesGetParts::Index(&[index]))
-                    .send()
-                    .await?;
-                let body = response.json::<Value>().await?;
-                Ok(body)
+/// 索引名作为路径段编码：/ ? # 空白等全部转义，避免拼出其他 API（如 logs/_delete_by_query?x=）；
+/// 保留多索引与通配常用的 , * :，日期表达式 <logs-{now/d}> 按官方要求转义后由服务端还原
+fn encode_index_segment(index: &str) -> String {
+    let mut encoded = String::with_capacity(index.len());
+    for byte in index.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b',' | b'*' | b':' => {
+                encoded.push(byte as char)
             }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
         }
     }
+    encoded
+}
 
-    pub async fn get_aliases(&self, index: &str) -> Result<Value> {
-        match self {
-            Self::V6(client, _) => {
-                let response = client
-                    .get(format!("{}/{}/_alias", self.get_base_url(), index))
-                    .send()
-                    .await?;
-                let body = response.json::<Value>().await?;
-                Ok(body)
-            }
-            Self::V8(client, _) => {
-                let response = client
-                    .indices()
-                    .get_alias(IndicesGetAliasParts::Index(&[index]))
-                    .send()
-                    .await?;
-                let body = response.json::<Value>().await?;
-                Ok(body)
-            }
+/// 以索引开头的请求路径，suffix 为 API 部分，如 /_search
+fn index_path(index: &str, suffix: &str) -> String {
+    format!("/{}{}", encode_index_segment(index), suffix)
+}
+
+/// 底层 HTTP 客户端：Elasticsearch 8.x 使用官方客户端（每个节点一个 Transport），其余版本使用通用 HTTP 客户端
+pub enum EsHttpClient {
+    V6(reqwest::Client),
+    V8(RwLock<HashMap<String, ES8>>),
+}
+
+pub struct MCPElasticsearch {
+    client: EsHttpClient,
+    config: ElasticsearchConfig,
+    version: EsVersion,
+    pool: EsNodePool,
+    timeout: Duration,
+}
+
+/// 原始响应：状态码与响应体文本
+pub struct EsRawResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+impl EsRawResponse {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// 解析响应体，非 2xx 时返回结构化的 EsError
+    pub fn json(&self) -> Result<Value> {
+        if !self.is_success() {
+            return Err(EsError::from_response(self.status, &self.body).into());
+        }
+        if self.body.trim().is_empty() {
+            return Ok(Value::Null);
         }
+        Ok(serde_json::from_str(&self.body)?)
     }
+}
 
-    pub async fn get_mapping(&self, index: &str) -> Result<Value> {
-        match self {
-            Self::V6(client, _) => {
-                let response = client
-                    .get(format!("{}/{}/_mapping", self.get_base_url(), index))
-                    .send()
-                    .await?;
-                let body = response.json::<Value>().await?;
-                Ok(body)
-            }
-            Self::V8(client, _) => {
-                let response = client
-                    .indices()
-                    .get_mapping(IndicesGetMappingParts::Index(&[index]))
-                    .send()
-                    .await?;
-                let body = response.json::<Value>().await?;
-                Ok(body)
+/// 配置中的节点列表：urls 非空时使用 urls，否则使用 url
+pub fn es_node_urls(config: &ElasticsearchConfig) -> Vec<String> {
+    if config.urls.is_empty() {
+        vec![config.url.clone()]
+    } else {
+        config.urls.clone()
+    }
+}
+
+/// 认证请求头：API Key 优先，其次 Bearer Token，最后用户名密码
+fn authorization_header(config: &ElasticsearchConfig) -> Option<String> {
+    if let Some(api_key) = config.api_key.as_deref().filter(|k| !k.is_empty()) {
+        // "id:api_key" 形式需要 base64 编码，否则视为已编码的值
+        let encoded = if api_key.contains(':') {
+            BASE64_STANDARD.encode(api_key)
+        } else {
+            api_key.to_string()
+        };
+        return Some(format!("ApiKey {}", encoded));
+    }
+    if let Some(token) = config.bearer_token.as_deref().filter(|t| !t.is_empty()) {
+        return Some(format!("Bearer {}", token));
+    }
+    if let (Some(username), Some(password)) = (&config.username, &config.password) {
+        let auth = BASE64_STANDARD.encode(format!("{}:{}", username, password));
+        return Some(format!("Basic {}", auth));
+    }
+    None
+}
+
+fn invalid_header_error(config: &ElasticsearchConfig) -> anyhow::Error {
+    anyhow!(
+        "Credentials of Elasticsearch '{}' contain characters that are not allowed in an HTTP header",
+        config.name
+    )
+}
+
+fn read_tls_file(config: &ElasticsearchConfig, path: &str, what: &str) -> Result<Vec<u8>> {
+    std::fs::read(path)
+        .map_err(|e| anyhow!("Failed to read {} '{}' of Elasticsearch '{}': {}", what, path, config.name, e))
+}
+
+fn build_http_client(config: &ElasticsearchConfig, timeout: Duration) -> Result<reqwest::Client> {
+    let mut client_builder = reqwest::Client::builder().timeout(timeout);
+
+    if let Some(auth) = authorization_header(config) {
+        let mut value = reqwest::header::HeaderValue::from_str(&auth).map_err(|_| invalid_header_error(config))?;
+        value.set_sensitive(true);
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(reqwest::header::AUTHORIZATION, value);
+        client_builder = client_builder.default_headers(headers);
+    }
+    if let Some(ca_cert) = &config.ca_cert {
+        let pem = read_tls_file(config, ca_cert, "CA certificate")?;
+        client_builder = client_builder.add_root_certificate(reqwest::Certificate::from_pem(&pem)?);
+    }
+    if let Some(client_cert) = &config.client_cert {
+        let der = read_tls_file(config, client_cert, "client certificate")?;
+        let password = config.client_cert_password.as_deref().unwrap_or("");
+        client_builder = client_builder.identity(reqwest::Identity::from_pkcs12_der(&der, password)?);
+    }
+    if config.skip_tls_verify {
+        client_builder = client_builder.danger_accept_invalid_certs(true);
+    }
+
+    Ok(client_builder.build()?)
+}
+
+/// 官方客户端的 Transport，认证与 TLS 选项与通用 HTTP 客户端保持一致
+fn build_transport(config: &ElasticsearchConfig, node: &str, timeout: Duration) -> Result<Transport> {
+    let url = url::Url::parse(node)?;
+    let conn_pool = SingleNodeConnectionPool::new(url);
+    let mut transport_builder = TransportBuilder::new(conn_pool).timeout(timeout);
+
+    // 认证通过默认请求头设置，Credentials 留给客户端证书使用
+    if let Some(auth) = authorization_header(config) {
+        let mut value = HeaderValue::from_str(&auth).map_err(|_| invalid_header_error(config))?;
+        value.set_sensitive(true);
+        transport_builder = transport_builder.header(AUTHORIZATION, value);
+    }
+    if let Some(client_cert) = &config.client_cert {
+        let der = read_tls_file(config, client_cert, "client certificate")?;
+        transport_builder = transport_builder.auth(Credentials::Certificate(ClientCertificate::Pkcs12(
+            der,
+            config.client_cert_password.clone(),
+        )));
+    }
+    if config.skip_tls_verify {
+        transport_builder = transport_builder.cert_validation(CertificateValidation::None);
+    } else if let Some(ca_cert) = &config.ca_cert {
+        let pem = read_tls_file(config, ca_cert, "CA certificate")?;
+        transport_builder = transport_builder.cert_validation(CertificateValidation::Full(Certificate::from_pem(&pem)?));
+    }
+
+    Ok(transport_builder.build()?)
+}
+
+/// 通过根路径识别集群版本与发行版（Elasticsearch / OpenSearch），依次尝试各节点
+async fn detect_version(client: &reqwest::Client, pool: &EsNodePool, config: &ElasticsearchConfig) -> Result<EsVersion> {
+    let mut last_error = None;
+    for node in pool.candidates() {
+        let response = match send_with_retry(|| client.get(&node).send()).await {
+            Ok(response) => response,
+            Err(e) => {
+                pool.mark_failed(&node);
+                last_error = Some(e);
+                continue;
             }
+        };
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(EsError::from_response(status.as_u16(), &body).into());
         }
+        let version = EsVersion::from_root_response(&serde_json::from_str(&body)?)?;
+        println!("[Elasticsearch] 配置 {} 识别到版本 {}", config.name, version);
+        return Ok(version);
     }
+    Err(last_error.unwrap_or_else(|| anyhow!("Elasticsearch '{}' has no node url", config.name)))
+}
 
-    pub async fn get_health(&self) -> Result<Value> {
-        match self {
-            Self::V6(client, _) => {
-                let response = client
-                    .get(format!("{}/_cluster/health", self.get_base_url()))
-                    .send()
-                    .await?;
-                let body = response.json::<Value>().await?;
-                Ok(body)
-            }
-            Self::V8(client, _) => {
-                let response = client
-                    .cluster()
-                    .health(elasticsearch::cluster::ClusterHealthParts::None)
-                    .send()
-                    .await?;
-                let body = response.json::<Value>().await?;
-                Ok(body)
+fn to_reqwest_method(method: Method) -> reqwest::Method {
+    match method {
+        Method::Get => reqwest::Method::GET,
+        Method::Put => reqwest::Method::PUT,
+        Method::Post => reqwest::Method::POST,
+        Method::Delete => reqwest::Method::DELETE,
+        Method::Head => reqwest::Method::HEAD,
+    }
+}
+
+impl MCPElasticsearch {
+    /// 创建客户端，配置未指定 version（或为 auto）时自动识别版本
+    pub async fn new(config: &ElasticsearchConfig) -> Result<Self> {
+        let timeout = Duration::from_secs(config.timeout_secs.unwrap_or_else(es_request_timeout_secs));
+        let pool = EsNodePool::new(es_node_urls(config), config.sniff);
+        let http_client = build_http_client(config, timeout)?;
+        let version = match config.version.as_deref().map(str::trim) {
+            Some(version) if !version.is_empty() && !version.eq_ignore_ascii_case("auto") => {
+                EsVersion::from_config(version)?
             }
+            _ => detect_version(&http_client, &pool, config).await?,
+        };
+
+        let client = if version.uses_official_client() {
+            EsHttpClient::V8(RwLock::new(HashMap::new()))
+        } else {
+            EsHttpClient::V6(http_client)
+        };
+        Ok(Self {
+            client,
+            config: config.clone(),
+            version,
+            pool,
+            timeout,
+        })
+    }
+
+    pub fn config(&self) -> &ElasticsearchConfig {
+        &self.config
+    }
+
+    pub fn version(&self) -> &EsVersion {
+        &self.version
+    }
+
+    pub fn nodes(&self) -> Vec<EsNodeStatus> {
+        self.pool.status()
+    }
+
+    /// 官方客户端按节点懒加载
+    fn v8_client(&self, clients: &RwLock<HashMap<String, ES8>>, node: &str) -> Result<ES8> {
+        if let Some(client) = clients.read().unwrap().get(node) {
+            return Ok(client.clone());
         }
+        let client = ES8::new(build_transport(&self.config, node, self.timeout)?);
+        clients.write().unwrap().insert(node.to_string(), client.clone());
+        Ok(client)
     }
 
-    pub async fn search(&self, index: &str, query: &str) -> Result<Value> {
-        match self {
-            Self::V6(client, _) => {
-                let query: Value = serde_json::from_str(query)?;
-                let response = client
-                    .post(format!("{}/{}/_search", self.get_base_url(), index))
-                    .json(&query)
-                    .send()
-                    .await?;
-                let body = response.json::<Value>().await?;
-                Ok(body)
+    /// 向指定节点发送请求（含 429 / 503 重试）
+    async fn send_to_node(&self, node: &str, method: Method, path: &str, body: Option<&Value>) -> Result<EsRawResponse> {
+        match &self.client {
+            EsHttpClient::V6(client) => {
+                let url = format!("{}{}", node, path);
+                let response = send_with_retry(|| {
+                    let request = client.request(to_reqwest_method(method), &url);
+                    match body {
+                        Some(body) => request.json(body).send(),
+                        None => request.send(),
+                    }
+                })
+                .await?;
+                Ok(EsRawResponse {
+                    status: response.status().as_u16(),
+                    body: response.text().await?,
+                })
             }
-            Self::V8(client, _) => {
-                let query: Value = serde_json::from_str(query)?;
-                let response = client
-                    .search(elasticsearch::SearchParts::Index(&[index]))
-                    .body(query)
-                    .send()
-                    .await?;
-                let body = response.json::<Value>().await?;
-                Ok(body)
+            EsHttpClient::V8(clients) => {
+                let client = self.v8_client(clients, node)?;
+                let transport = client.transport();
+                let response = send_with_retry(|| {
+                    transport.send(
+                        method,
+                        path,
+                        HeaderMap::new(),
+                        None::<&()>,
+                        body.map(|b| JsonBody::new(b.clone())),
+                        None,
+                    )
+                })
+                .await?;
+                Ok(EsRawResponse {
+                    status: response.status_code().as_u16(),
+                    body: response.text().await?,
+                })
             }
         }
     }
 
-    pub async fn get_version(&self) -> Result<String> {
-        match self {
-            Self::V6(client, _) => {
-                let response = client
-                    .get(self.get_base_url())
-                    .send()
-                    .await?;
-                let body = response.json::<Value>().await?;
-                let version = body["version"]["number"]
-                    .as_str()
-                    .unwrap_or("unknown");
-                Ok(version.to_string())
+    /// 开启嗅探时按间隔刷新节点列表，失败只记录日志
+    async fn sniff_if_due(&self) {
+        if !self.pool.should_sniff() {
+            return;
+        }
+        for node in self.pool.candidates() {
+            match self.send_to_node(&node, Method::Get, "/_nodes/http", None).await {
+                Ok(response) if response.is_success() => {
+                    if let Ok(body) = response.json() {
+                        self.pool.update_nodes(parse_sniffed_nodes(&body, self.pool.scheme()));
+                    }
+                    return;
+                }
+                Ok(response) => {
+                    eprintln!("[Elasticsearch] 节点嗅探失败 {}: {}", response.status, response.body);
+                    return;
+                }
+                Err(_) => continue,
             }
-            Self::V8(client, _) => {
-                let response = client
-                    .info()
-                    .send()
-                    .await?;
-                let body = response.json::<Value>().await?;
-                let version = body["version"]["number"]
-                    .as_str()
-                    .unwrap_or("unknown");
-                Ok(version.to_string())
+        }
+    }
+
+    /// 发送请求：轮询节点，连接失败或 502/503/504 时摘除该节点并切换到下一个节点
+    pub async fn request(&self, method: Method, path: &str, body: Option<&Value>) -> Result<EsRawResponse> {
+        self.sniff_if_due().await;
+        let mut last_error = None;
+        for node in self.pool.candidates() {
+            match self.send_to_node(&node, method, path, body).await {
+                Ok(response) if matches!(response.status, 502..=504) => {
+                    self.pool.mark_failed(&node);
+                    eprintln!("[Elasticsearch] 节点 {} 返回 {}", node, response.status);
+                    last_error = Some(EsError::from_response(response.status, &response.body).into());
+                }
+                Ok(response) => {
+                    self.pool.mark_healthy(&node);
+                    return Ok(response);
+                }
+                Err(e) => {
+                    self.pool.mark_failed(&node);
+                    last_error = Some(e);
+                }
             }
         }
+        Err(last_error.unwrap_or_else(|| anyhow!("Elasticsearch '{}' has no node url", self.config.name)))
     }
 
-    fn get_base_url(&self) -> String {
-        match self {
-            Self::V6(_, config) => config.url.clone(),
-            Self::V8(_, config) => config.url.clone(),
+    pub async fn index_exists(&self, index: &str) -> Result<bool> {
+        // 仅 404 表示不存在，认证失败等其他错误需要如实返回
+        let response = self.request(Method::Head, &index_path(index, ""), None).await?;
+        match response.status {
+            404 => Ok(false),
+            _ if response.is_success() => Ok(true),
+            status => Err(EsError::from_response(status, &response.body).into()),
         }
     }
+
+    pub async fn get_index(&self, index: &str) -> Result<Value> {
+        let body = self.request(Method::Get, &index_path(index, ""), None).await?.json()?;
+        Ok(normalize_mappings(body, self.version()))
+    }
+
+    pub async fn get_aliases(&self, index: &str) -> Result<Value> {
+        self.request(Method::Get, &index_path(index, "/_alias"), None).await?.json()
+    }
+
+    pub async fn get_mapping(&self, index: &str) -> Result<Value> {
+        let body = self.request(Method::Get, &index_path(index, "/_mapping"), None).await?.json()?;
+        Ok(normalize_mappings(body, self.version()))
+    }
+
+    pub async fn get_health(&self) -> Result<Value> {
+        self.request(Method::Get, "/_cluster/health", None).await?.json()
+    }
+
+    pub async fn search(&self, index: &str, query: &Value) -> Result<Value> {
+        let body = self
+            .request(Method::Post, &index_path(index, "/_search"), Some(query))
+            .await?
+            .json()?;
+        Ok(normalize_search_response(body))
+    }
+
+    /// 打开 point-in-time，返回 PIT id；OpenSearch 使用 /_search/point_in_time，响应字段为 pit_id
+    pub async fn open_pit(&self, index: &str, keep_alive: &str) -> Result<String> {
+        let (path, id_field) = match self.version.distribution {
+            EsDistribution::Elasticsearch => (format!("{}?keep_alive={}", index_path(index, "/_pit"), keep_alive), "id"),
+            EsDistribution::OpenSearch => (
+                format!("{}?keep_alive={}", index_path(index, "/_search/point_in_time"), keep_alive),
+                "pit_id",
+            ),
+        };
+        let body = self.request(Method::Post, &path, None).await?.json()?;
+        body[id_field]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("Open point-in-time response has no {}: {}", id_field, body))
+    }
+
+    pub async fn close_pit(&self, pit_id: &str) -> Result<()> {
+        let (path, body) = match self.version.distribution {
+            EsDistribution::Elasticsearch => ("/_pit", json!({ "id": pit_id })),
+            EsDistribution::OpenSearch => ("/_search/point_in_time", json!({ "pit_id": [pit_id] })),
+        };
+        self.request(Method::Delete, path, Some(&body)).await?.json()?;
+        Ok(())
+    }
+
+    /// PIT 搜索不指定索引，索引由 PIT 决定
+    pub async fn search_pit(&self, query: &Value) -> Result<Value> {
+        let body = self.request(Method::Post, "/_search", Some(query)).await?.json()?;
+        Ok(normalize_search_response(body))
+    }
+
+    pub async fn scroll_start(&self, index: &str, query: &Value, keep_alive: &str) -> Result<Value> {
+        let path = format!("{}?scroll={}", index_path(index, "/_search"), keep_alive);
+        let body = self.request(Method::Post, &path, Some(query)).await?.json()?;
+        Ok(normalize_search_response(body))
+    }
+
+    pub async fn scroll_next(&self, scroll_id: &str, keep_alive: &str) -> Result<Value> {
+        let query = json!({ "scroll": keep_alive, "scroll_id": scroll_id });
+        let body = self.request(Method::Post, "/_search/scroll", Some(&query)).await?.json()?;
+        Ok(normalize_search_response(body))
+    }
+
+    pub async fn clear_scroll(&self, scroll_id: &str) -> Result<()> {
+        let query = json!({ "scroll_id": [scroll_id] });
+        self.request(Method::Delete, "/_search/scroll", Some(&query))
+            .await?
+            .json()?;
+        Ok(())
+    }
+
+    pub async fn get_version(&self) -> Result<String> {
+        let body = self.request(Method::Get, "/", None).await?.json()?;
+        let version = body["version"]["number"]
+            .as_str()
+            .unwrap_or("unknown");
+        Ok(version.to_string())
+    }
 }
 
 // 配置管理函数
@@ -246,7 +601,7 @@ pub async fn es_list_configs() -> Result<ToolResponseContent> {
         .iter()
         .map(|c| format!(
             "Name: {}, URL: {}, Version: {}, Environment: {}, Description: {}",
-            c.name, c.url, c.version, c.environment, c.description
+            c.name, c.url, c.version.as_deref().unwrap_or("auto"), c.environment, c.description
         ))
         .collect();
     Ok(tool_text_content!(config_list.join("\n")))
@@ -257,11 +612,7 @@ pub async fn es_list_configs() -> Result<ToolResponseContent> {
     description = "Check if a specific Elasticsearch index exists in the given configuration. Takes the configuration name and index name as parameters, and returns a boolean result. Useful for automated tools (such as cursor) to validate index presence before performing further actions."
 )]
 pub async fn es_index_exists(config_name: String, index: String) -> Result<ToolResponseContent> {
-    let config = crate::utils::nacos_config::get_config_inner()?;
-    let es_config = get_es_config(&config, &config_name)
-        .ok_or_else(|| anyhow::anyhow!("Configuration not found: {}", config_name))?;
-
-    let es = MCPElasticsearch::new(es_config)?;
+    let es = get_es_client(&config_name).await?;
     let exists = es.index_exists(&index).await?;
     Ok(tool_text_content!(format!("Index {} exists: {}", index, exists)))
 }
@@ -271,11 +622,7 @@ pub async fn es_index_exists(config_name: String, index: String) -> Result<ToolR
     description = "Retrieve detailed information about a specific Elasticsearch index, including settings, mappings, and metadata, by specifying the configuration and index name. Enables automated tools (such as cursor) to inspect index structure and properties for advanced queries or validation."
 )]
 pub async fn es_get_index(config_name: String, index: String) -> Result<ToolResponseContent> {
-    let config = crate::utils::nacos_config::get_config_inner()?;
-    let es_config = get_es_config(&config, &config_name)
-        .ok_or_else(|| anyhow::anyhow!("Configuration not found: {}", config_name))?;
-
-    let es = MCPElasticsearch::new(es_config)?;
+    let es = get_es_client(&config_name).await?;
     let info = es.get_index(&index).await?;
     Ok(tool_text_content!(serde_json::to_string_pretty(&info)?))
 }
@@ -285,11 +632,7 @@ pub async fn es_get_index(config_name: String, index: String) -> Result<ToolResp
     description = "Get all aliases associated with a specific Elasticsearch index in the given configuration. Returns alias mappings and related metadata, which is useful for automated tools (such as cursor) to resolve index references and manage index routing."
 )]
 pub async fn es_get_aliases(config_name: String, index: String) -> Result<ToolResponseContent> {
-    let config = crate::utils::nacos_config::get_config_inner()?;
-    let es_config = get_es_config(&config, &config_name)
-        .ok_or_else(|| anyhow::anyhow!("Configuration not found: {}", config_name))?;
-
-    let es = MCPElasticsearch::new(es_config)?;
+    let es = get_es_client(&config_name).await?;
     let aliases = es.get_aliases(&index).await?;
     Ok(tool_text_content!(serde_json::to_string_pretty(&aliases)?))
 }
@@ -299,41 +642,343 @@ pub async fn es_get_aliases(config_name: String, index: String) -> Result<ToolRe
     description = "Fetch the mapping definition for a specific Elasticsearch index, including field types and structure, by configuration and index name. This allows automated tools (such as cursor) to analyze index schemas and validate document compatibility."
 )]
 pub async fn es_get_mapping(config_name: String, index: String) -> Result<ToolResponseContent> {
-    let config = crate::utils::nacos_config::get_config_inner()?;
-    let es_config = get_es_config(&config, &config_name)
-        .ok_or_else(|| anyhow::anyhow!("Configuration not found: {}", config_name))?;
-
-    let es = MCPElasticsearch::new(es_config)?;
+    let es = get_es_client(&config_name).await?;
     let mapping = es.get_mapping(&index).await?;
     Ok(tool_text_content!(serde_json::to_string_pretty(&mapping)?))
 }
 
 #[tool(
     name = "ESGetHealth",
-    description = "Get the health status of the Elasticsearch cluster for a given configuration. Returns cluster health metrics and status, enabling automated tools (such as cursor) to monitor cluster availability and performance."
+    description = "Get the health status of the Elasticsearch cluster for a given configuration. Returns cluster health metrics and status plus client_nodes (the configured or sniffed nodes and whether each is currently excluded after failures), enabling automated tools (such as cursor) to monitor cluster availability and performance."
 )]
 pub async fn es_get_health(config_name: String) -> Result<ToolResponseContent> {
-    let config = crate::utils::nacos_config::get_config_inner()?;
-    let es_config = get_es_config(&config, &config_name)
-        .ok_or_else(|| anyhow::anyhow!("Configuration not found: {}", config_name))?;
-
-    let es = MCPElasticsearch::new(es_config)?;
-    let health = es.get_health().await?;
+    let es = get_es_client(&config_name).await?;
+    let mut health = es.get_health().await?;
+    // 附带客户端侧的节点状态（轮询 / 摘除情况）
+    health["client_nodes"] = serde_json::to_value(es.nodes())?;
     Ok(tool_text_content!(serde_json::to_string_pretty(&health)?))
 }
 
 #[tool(
     name = "ESSearch",
-    description = "Execute a search query on a specified Elasticsearch index using the given configuration. Accepts a JSON query string and returns the search results. This is essential for automated tools (such as cursor) to perform dynamic data retrieval and analysis."
+    description = "Execute a search query on a specified Elasticsearch index using the given configuration. Accepts a JSON query string and returns the search results, with hits.total normalized to {\"value\", \"relation\"} on every version. size is capped by the server (ES_MAX_SEARCH_SIZE) and responses larger than ES_MAX_RESPONSE_BYTES are truncated with a _truncated marker; use compact mode and _source filtering to keep results small. With validate=warn or strict the query is first checked against the fields catalog (fields exist, sort fields sortable, aggregation fields aggregatable); issues are returned in _validation, and strict mode rejects queries with errors without contacting the cluster.",
+    params(
+        config_name = "Elasticsearch configuration name from ESListConfigs",
+        index = "Index name or pattern",
+        query = "Search request body as a JSON string, e.g. {\"query\":{\"match_all\":{}},\"size\":10}",
+        source_includes = "Optional list of _source fields to return, wildcards allowed",
+        source_excludes = "Optional list of _source fields to exclude, wildcards allowed",
+        compact = "If true, return only total, hits[]._source and aggregations",
+        validate = "Fields catalog validation: off, warn or strict; defaults to ES_QUERY_VALIDATION (off)"
+    )
 )]
-pub async fn es_search(config_name: String, index: String, query: String) -> Result<ToolResponseContent> {
-    let config = crate::utils::nacos_config::get_config_inner()?;
-    let es_config = get_es_config(&config, &config_name)
-        .ok_or_else(|| anyhow::anyhow!("Configuration not found: {}", config_name))?;
-
-    let es = MCPElasticsearch::new(es_config)?;
+pub async fn es_search(
+    config_name: String,
+    index: String,
+    query: String,
+    source_includes: Option<Vec<String>>,
+    source_excludes: Option<Vec<String>>,
+    compact: Option<bool>,
+    validate: Option<String>,
+) -> Result<ToolResponseContent> {
+    let mut query: Value = serde_json::from_str(&query)?;
+    let validation = match QueryValidationMode::resolve(validate.as_deref())? {
+        QueryValidationMode::Off => None,
+        mode => {
+            let catalog = crate::utils::nacos_config::get_es_fields_config().ok();
+            let catalog_index = catalog.as_ref().and_then(|c| find_catalog_index(c, &index));
+            let validation = validate_search_query(catalog_index, &query);
+            if mode == QueryValidationMode::Strict && !validation.is_valid() {
+                return Err(anyhow!(
+                    "Query rejected by fields catalog validation:\n{}",
+                    serde_json::to_string_pretty(&validation)?
+                ));
+            }
+            Some(validation).filter(|v| v.has_issues())
+        }
+    };
+    let es = get_es_client(&config_name).await?;
+    let notes = prepare_search_query(
+        &mut query,
+        &source_includes.unwrap_or_default(),
+        &source_excludes.unwrap_or_default(),
+    )?;
     let results = es.search(&index, &query).await?;
-    Ok(tool_text_content!(serde_json::to_string_pretty(&results)?))
+
+    let compact = compact.unwrap_or(false);
+    let mut results = if compact {
+        compact_search_response(&results)
+    } else {
+        results
+    };
+    if !notes.is_empty() {
+        results["_adjustments"] = serde_json::to_value(&notes)?;
+    }
+    if let Some(validation) = validation {
+        results["_validation"] = serde_json::to_value(&validation)?;
+    }
+    Ok(tool_text_content!(render_with_budget(results, compact, es_max_response_bytes())?))
+}
+
+/// 释放分页会话对应的 PIT / scroll 上下文，失败只记录日志（到期后 Elasticsearch 也会自行释放）
+async fn release_page_session(session: &PageSession) {
+    let result = match get_es_client(&session.config_name).await {
+        Ok(es) => match &session.cursor {
+            PageCursor::Pit { pit_id, .. } => es.close_pit(pit_id).await,
+            PageCursor::Scroll { scroll_id: Some(scroll_id) } => es.clear_scroll(scroll_id).await,
+            PageCursor::Scroll { scroll_id: None } => Ok(()),
+        },
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        eprintln!("[Elasticsearch] 释放分页上下文失败 {}/{}: {}", session.config_name, session.index, e);
+    }
+}
+
+async fn release_expired_page_sessions() {
+    for session in take_expired_sessions() {
+        println!("[Elasticsearch] 分页会话已过期，释放上下文 {}/{}", session.config_name, session.index);
+        release_page_session(&session).await;
+    }
+}
+
+/// 新建分页会话：去掉 from、限制 size、补默认排序，支持时打开 PIT
+async fn open_page_session(
+    es: &MCPElasticsearch,
+    config_name: &str,
+    index: &str,
+    query: Option<String>,
+    page_size: Option<u64>,
+) -> Result<(PageSession, Vec<String>)> {
+    let mut body: Value = match query {
+        Some(query) if !query.trim().is_empty() => serde_json::from_str(&query)?,
+        _ => json!({ "query": { "match_all": {} } }),
+    };
+    let object = body
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("Search query must be a JSON object"))?;
+    let mut notes = Vec::new();
+    if object.remove("from").is_some() {
+        notes.push("from is not supported when paging and was removed".to_string());
+    }
+    for key in ["search_after", "pit", "scroll"] {
+        if object.remove(key).is_some() {
+            notes.push(format!("{} is managed by the paging session and was removed", key));
+        }
+    }
+    match page_size {
+        Some(size) => {
+            object.insert("size".to_string(), json!(size));
+        }
+        None => {
+            object.entry("size").or_insert_with(|| json!(es_max_search_size()));
+        }
+    }
+    notes.extend(prepare_search_query(&mut body, &[], &[])?);
+    let page_size = body["size"].as_u64().unwrap_or(0);
+    if page_size == 0 {
+        return Err(anyhow!("Page size must be greater than 0"));
+    }
+
+    apply_default_sort(&mut body, es.version());
+    let cursor = if es.version().supports_pit() {
+        PageCursor::Pit {
+            pit_id: es.open_pit(index, &keep_alive_param()).await?,
+            search_after: None,
+        }
+    } else {
+        PageCursor::Scroll { scroll_id: None }
+    };
+    let session = PageSession {
+        config_name: config_name.to_string(),
+        index: index.to_string(),
+        body,
+        cursor,
+        page_size,
+        pages: 0,
+        returned_hits: 0,
+    };
+    Ok((session, notes))
+}
+
+/// 取下一页并推进游标（PIT id / scroll id 可能在每次请求后变化）
+async fn fetch_page(es: &MCPElasticsearch, session: &mut PageSession) -> Result<Value> {
+    let keep_alive = keep_alive_param();
+    match &mut session.cursor {
+        PageCursor::Pit { pit_id, search_after } => {
+            let mut query = session.body.clone();
+            query["pit"] = json!({ "id": pit_id, "keep_alive": keep_alive });
+            if let Some(after) = search_after.as_ref() {
+                query["search_after"] = after.clone();
+            }
+            let response = es.search_pit(&query).await?;
+            if let Some(id) = response["pit_id"].as_str() {
+                *pit_id = id.to_string();
+            }
+            Ok(response)
+        }
+        PageCursor::Scroll { scroll_id } => {
+            let response = match scroll_id.as_deref() {
+                Some(id) => es.scroll_next(id, &keep_alive).await?,
+                None => es.scroll_start(&session.index, &session.body, &keep_alive).await?,
+            };
+            if let Some(id) = response["_scroll_id"].as_str() {
+                *scroll_id = Some(id.to_string());
+            }
+            Ok(response)
+        }
+    }
+}
+
+/// 取下一页并按字节预算组装结果
+async fn next_page(config_name: &str, session: &mut PageSession, compact: bool) -> Result<PageResult> {
+    let es = get_es_client(config_name).await?;
+    let response = fetch_page(&es, session).await?;
+    build_page(session, &response, compact, es_max_response_bytes())
+}
+
+#[tool(
+    name = "ESSearchPage",
+    description = "Page through all hits of an Elasticsearch search beyond the 10,000 from/size window. The first call takes config_name, index and an optional query (JSON body with query/sort/_source, from is ignored) and returns the first page plus an opaque continuation_token; pass the same config_name, index and the token to get the next page. Uses point-in-time + search_after on Elasticsearch 7.10+ and OpenSearch 2.4+, and scroll on older versions. Pages over ES_MAX_RESPONSE_BYTES return fewer hits and continue on the next page with point-in-time, but fail with scroll (use compact, _source filtering or a smaller page_size). The token stays valid for ES_PAGE_KEEP_ALIVE_SECS after each page; continuation_token is null on the last page and the server-side context is released. Pass close=true with a token to release it early.",
+    params(
+        config_name = "Elasticsearch configuration name from ESListConfigs",
+        index = "Index name or pattern",
+        query = "Search request body as a JSON string for the first page, defaults to match_all; ignored when continuation_token is given",
+        page_size = "Hits per page, capped by ES_MAX_SEARCH_SIZE; defaults to the query size or the cap",
+        continuation_token = "Token returned by the previous page",
+        compact = "If true, return only hits[]._source",
+        close = "If true, release the paging context of continuation_token without fetching"
+    )
+)]
+pub async fn es_search_page(
+    config_name: String,
+    index: String,
+    query: Option<String>,
+    page_size: Option<u32>,
+    continuation_token: Option<String>,
+    compact: Option<bool>,
+    close: Option<bool>,
+) -> Result<ToolResponseContent> {
+    release_expired_page_sessions().await;
+
+    let (token, mut session, notes) = match continuation_token {
+        Some(token) => {
+            let session = take_session(&token)?;
+            if session.config_name != config_name || session.index != index {
+                let (expected_config, expected_index) = (session.config_name.clone(), session.index.clone());
+                save_session(&token, session);
+                return Err(anyhow!(
+                    "Continuation token '{}' belongs to {}/{}, not {}/{}",
+                    token, expected_config, expected_index, config_name, index
+                ));
+            }
+            if close.unwrap_or(false) {
+                release_page_session(&session).await;
+                return Ok(tool_text_content!(serde_json::to_string_pretty(&json!({
+                    "continuation_token": token,
+                    "closed": true,
+                }))?));
+            }
+            (token, session, Vec::new())
+        }
+        None => {
+            let es = get_es_client(&config_name).await?;
+            let (session, notes) = open_page_session(&es, &config_name, &index, query, page_size.map(u64::from)).await?;
+            (new_page_token(), session, notes)
+        }
+    };
+
+    // 会话已取出，之后任何失败都要释放 PIT / scroll 上下文
+    let PageResult { mut page, finished } = match next_page(&config_name, &mut session, compact.unwrap_or(false)).await {
+        Ok(result) => result,
+        Err(e) => {
+            release_page_session(&session).await;
+            return Err(e);
+        }
+    };
+    if !notes.is_empty() {
+        page["_adjustments"] = json!(notes);
+    }
+
+    if finished {
+        release_page_session(&session).await;
+        page["continuation_token"] = Value::Null;
+    } else {
+        let expires_in_secs = save_session(&token, session);
+        page["continuation_token"] = json!(token);
+        page["expires_in_secs"] = json!(expires_in_secs);
+    }
+    // 分页结果的命中列表位于顶层 hits
+    Ok(tool_text_content!(render_with_budget(page, true, es_max_response_bytes())?))
+}
+
+/// 未指定时间范围时的默认聚合窗口
+const DEFAULT_AGG_TIME_RANGE: &str = "1h";
+
+#[tool(
+    name = "ESAggregate",
+    description = "Count or measure documents by time and/or fields without writing aggregation DSL. Builds a range filter on time_field, an optional date_histogram (interval) and nested terms aggregations (group_by, top ES_AGG_TERMS_SIZE per field) with the metric, using the right interval syntax for the cluster version. Fields are checked against the fields catalog (must be aggregatable). Returns a flat table {columns, rows, total_hits, warnings} plus the generated request.",
+    params(
+        config_name = "Elasticsearch configuration name from ESListConfigs",
+        index = "Index name or pattern",
+        time_field = "Date field used for the time range and histogram, e.g. @timestamp",
+        time_range = "Time range expression such as 'last 1h', '15m', 'today', 'yesterday' or '2024-05-01 10:00 to 2024-05-01 12:00'; defaults to the last hour",
+        group_by = "Optional list of fields to group by, outermost first",
+        interval = "Optional histogram interval, e.g. 5m, 1h, 1d (fixed) or hour, day, week, month (calendar)",
+        metric = "count (default), avg:<field>, sum:<field> or percentiles:<field>[:50,95,99]"
+    )
+)]
+pub async fn es_aggregate(
+    config_name: String,
+    index: String,
+    time_field: String,
+    time_range: Option<String>,
+    group_by: Option<Vec<String>>,
+    interval: Option<String>,
+    metric: Option<String>,
+) -> Result<ToolResponseContent> {
+    let metric = AggMetric::parse(metric.as_deref().unwrap_or("count"))?;
+    let group_by = group_by.unwrap_or_default();
+    let interval = interval.map(|i| i.trim().to_string()).filter(|i| !i.is_empty());
+
+    // 字段目录未加载或未定义该索引时只给出警告
+    let catalog = crate::utils::nacos_config::get_es_fields_config().ok();
+    let catalog_index = catalog.as_ref().and_then(|c| find_catalog_index(c, &index));
+    let mut fields: Vec<&str> = vec![time_field.as_str()];
+    fields.extend(group_by.iter().map(String::as_str));
+    fields.extend(metric.field());
+    let warnings = check_aggregatable_fields(catalog_index, &fields)?;
+
+    let time_range = time_range
+        .as_deref()
+        .map(str::trim)
+        .filter(|r| !r.is_empty())
+        .unwrap_or(DEFAULT_AGG_TIME_RANGE);
+    let (from, to, tz) = resolve_time_range(time_range, resolve_time_zone(None)?, Utc::now())?;
+    let request = AggregationRequest {
+        time_field,
+        from_millis: from.timestamp_millis(),
+        to_millis: to.timestamp_millis(),
+        time_zone: tz.name().to_string(),
+        interval,
+        group_by,
+        metric,
+    };
+
+    let es = get_es_client(&config_name).await?;
+    let query = build_aggregation_query(&request, es.version());
+    let response = es.search(&index, &query).await?;
+    let mut table = flatten_aggregation_response(&request, &response);
+    table.warnings.splice(0..0, warnings);
+
+    let mut result = serde_json::to_value(&table)?;
+    result["time_range"] = json!({
+        "from": from.with_timezone(&tz).to_rfc3339(),
+        "to": to.with_timezone(&tz).to_rfc3339(),
+        "time_zone": tz.name(),
+    });
+    result["request"] = query;
+    Ok(tool_text_content!(render_with_budget(result, false, es_max_response_bytes())?))
 }
 
 #[tool(
@@ -341,11 +986,49 @@ pub async fn es_search(config_name: String, index: String, query: String) -> Res
     description = "Retrieve the version information of the Elasticsearch cluster for a given configuration. Returns the version string, which is important for automated tools (such as cursor) to ensure compatibility and select appropriate features."
 )]
 pub async fn es_get_version(config_name: String) -> Result<ToolResponseContent> {
-    let config = crate::utils::nacos_config::get_config_inner()?;
-    let es_config = get_es_config(&config, &config_name)
-        .ok_or_else(|| anyhow::anyhow!("Configuration not found: {}", config_name))?;
-
-    let es = MCPElasticsearch::new(es_config)?;
+    let es = get_es_client(&config_name).await?;
     let version_info = es.get_version().await?;
-    Ok(tool_text_content!(format!("Version: {}", version_info)))
+    Ok(tool_text_content!(format!(
+        "Version: {} ({})",
+        version_info,
+        es.version().distribution_name()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_path_encodes_the_index_segment() {
+        assert_eq!(index_path("logs-*,metrics-2024.06", "/_search"), "/logs-*,metrics-2024.06/_search");
+        assert_eq!(index_path("remote:logs", "/_mapping"), "/remote:logs/_mapping");
+        assert_eq!(
+            index_path("logs/_delete_by_query?x=", "/_search"),
+            "/logs%2F_delete_by_query%3Fx%3D/_search"
+        );
+        assert_eq!(index_path("a b#c%", ""), "/a%20b%23c%25");
+        assert_eq!(index_path("<logs-{now/d}>", "/_search"), "/%3Clogs-%7Bnow%2Fd%7D%3E/_search");
+    }
+
+    #[test]
+    fn retry_after_seconds_and_http_date() {
+        let now = chrono::DateTime::parse_from_rfc3339("2024-06-01T10:00:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(parse_retry_after("5", now), Some(Duration::from_secs(5)));
+        assert_eq!(
+            parse_retry_after("Sat, 01 Jun 2024 10:00:12 GMT", now),
+            Some(Duration::from_secs(12))
+        );
+        assert_eq!(parse_retry_after("Sat, 01 Jun 2024 09:59:00 GMT", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn retry_delay_is_capped() {
+        assert_eq!(retry_delay(0, Some("2")), Duration::from_secs(2));
+        assert_eq!(retry_delay(0, Some("3600")), ES_RETRY_MAX_DELAY);
+        assert_eq!(retry_delay(0, None), ES_RETRY_BASE_DELAY);
+        assert_eq!(retry_delay(2, Some("invalid")), ES_RETRY_BASE_DELAY * 4);
+        assert_eq!(retry_delay(20, None), ES_RETRY_MAX_DELAY);
+    }
 }
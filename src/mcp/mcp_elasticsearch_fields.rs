@@ -1,5 +1,12 @@
+use crate::mcp::mcp_elasticsearch::get_es_client;
+use crate::utils::config::ElasticsearchFieldsConfig;
+use crate::utils::es_fields_catalog::{
+    catalog_changes, fields_from_mappings, find_catalog_index, merge_catalog_index, validate_search_query,
+};
 use crate::utils::nacos_config::get_es_fields_config as get_config;
+use crate::utils::nacos_publish::unified_diff;
 use anyhow::Result;
+use serde_json::json;
 use mcp_core::tool_text_content;
 use mcp_core::types::ToolResponseContent;
 use mcp_core_macros::tool;
@@ -30,3 +37,78 @@ pub async fn get_es_index_fields(index_name: String) -> Result<ToolResponseConte
     let yaml = serde_yaml::to_string(&index)?;
     Ok(tool_text_content!(yaml))
 }
+
+#[tool(
+    name = "ValidateEsQuery",
+    description = "Check an Elasticsearch search request body against the fields catalog without running it: every field referenced in query/post_filter must be defined for the index, sort fields must be sortable and aggregation fields aggregatable. Returns {catalog_index, errors, warnings} with the JSON path of each problem, e.g. sort[0] or aggs.by_host.terms.",
+    params(
+        index_name = "Index name or pattern, matched against the catalog (wildcard catalog names such as logs-* are supported)",
+        query = "Search request body as a JSON string"
+    )
+)]
+pub async fn validate_es_query(index_name: String, query: String) -> Result<ToolResponseContent> {
+    let config = get_config()?;
+    let query: serde_json::Value = serde_json::from_str(&query)?;
+    let validation = validate_search_query(find_catalog_index(&config, &index_name), &query);
+    Ok(tool_text_content!(serde_json::to_string_pretty(&validation)?))
+}
+
+#[tool(
+    name = "GenerateEsFieldsCatalog",
+    description = "Rebuild the Elasticsearch fields catalog (elasticsearch-fields.yml) from live mappings. For each index it reads the mapping, flattens object and multi-fields (e.g. message.keyword), infers type and is_sortable/is_aggregatable (keyword, numeric, date, ip, boolean with doc_values; text only with fielddata), keeps human-written descriptions and is_multi_value, and lists added, removed and changed fields. mode=diff (default) returns the changes and a unified diff against the current catalog; mode=generate returns the full updated catalog YAML. Nothing is written; publish the result with PublishNacosConfig or save it to ES_FIELDS_FILE.",
+    params(
+        config_name = "Elasticsearch configuration name from ESListConfigs",
+        indices = "Optional list of catalog index names or patterns to regenerate; defaults to every index in the catalog",
+        mode = "diff (default) or generate"
+    )
+)]
+pub async fn generate_es_fields_catalog(
+    config_name: String,
+    indices: Option<Vec<String>>,
+    mode: Option<String>,
+) -> Result<ToolResponseContent> {
+    let generate = match mode.as_deref().map(str::trim).unwrap_or("diff") {
+        "" | "diff" => false,
+        "generate" => true,
+        other => return Err(anyhow::anyhow!("Unknown mode '{}', expected diff or generate", other)),
+    };
+    // 字段目录尚未加载时从空目录开始生成
+    let catalog = get_config().unwrap_or(ElasticsearchFieldsConfig { indices: Vec::new() });
+    let names = indices
+        .filter(|names| !names.is_empty())
+        .unwrap_or_else(|| catalog.indices.iter().map(|index| index.name.clone()).collect());
+    if names.is_empty() {
+        return Err(anyhow::anyhow!("No indices given and the fields catalog is empty"));
+    }
+
+    let es = get_es_client(&config_name).await?;
+    let mut updated = catalog.clone();
+    let mut report = Vec::new();
+    for name in names {
+        let existing = find_catalog_index(&catalog, &name);
+        let index_name = existing.map(|index| index.name.clone()).unwrap_or(name);
+        let mapping = es.get_mapping(&index_name).await?;
+        let (fields, warnings) = fields_from_mappings(&mapping);
+        let index = merge_catalog_index(&index_name, existing, fields);
+        report.push(json!({
+            "index": index_name,
+            "changes": catalog_changes(existing, &index),
+            "warnings": warnings,
+        }));
+        match updated.indices.iter_mut().find(|idx| idx.name == index_name) {
+            Some(slot) => *slot = index,
+            None => updated.indices.push(index),
+        }
+    }
+
+    let updated_yaml = serde_yaml::to_string(&updated)?;
+    if generate {
+        return Ok(tool_text_content!(updated_yaml));
+    }
+    let current_yaml = serde_yaml::to_string(&catalog)?;
+    let result = json!({
+        "indices": report,
+        "diff": unified_diff(&current_yaml, &updated_yaml),
+    });
+    Ok(tool_text_content!(serde_json::to_string_pretty(&result)?))
+}
@@ -2,28 +2,533 @@ use anyhow::Result;
 use mcp_core::tool_text_content;
 use mcp_core::types::ToolResponseContent;
 use mcp_core_macros::tool;
+use crate::utils::config::{AppConfig, NacosConfig, NacosDataId};
+use crate::utils::config_format::{
+    detect_format, diff_configs, flatten_config_with_prefix, merged_documents_note, parse_config, select_path,
+    ConfigFormat,
+};
+use crate::utils::nacos_client::{
+    default_nacos_cluster, get_naming_service, nacos_auth_mode, resolve_nacos_cluster,
+    DEFAULT_NACOS_CLUSTER,
+};
 use crate::utils::nacos_config::{
-    get_nacos_config_by, get_nacos_service_info_by,
+    get_config_inner, get_nacos_config_by, get_nacos_service_info_by,
+};
+use crate::utils::nacos_naming::{
+    group_instances_by_cluster, list_all_service_names, summarize_services,
 };
-use serde_json;
+use crate::utils::nacos_open_api::{open_api_supported, NacosOpenApi};
+use crate::utils::nacos_publish::{apply_publish, preview_publish};
+use serde_json::{json, Value};
+
+const MAX_PAGE_SIZE: u32 = 100;
+const MAX_MATCHED_LINES: usize = 5;
 
 #[tool(
     name = "GetNacosConfigByClient",
-    description = "Get Nacos configuration information for specified namespace and data_id",
-    params(namespace = "Nacos namespace", data_id = "Nacos data id", group = "Nacos group, defaults to DEFAULT_GROUP")
+    description = "Get Nacos configuration information for specified namespace and data_id. Returns the raw content by default; for large configs pass path to return only a subtree (YAML, properties, JSON and XML are parsed), and/or flatten=true to list all dotted keys with their values. Multi-document YAML is merged (later documents win) and the output starts with a '# Note:' line saying so",
+    params(
+        namespace = "Nacos namespace",
+        data_id = "Nacos data id",
+        group = "Nacos group, defaults to DEFAULT_GROUP",
+        cluster = "Nacos cluster name from ListNacosClusters, defaults to the default cluster",
+        path = "Optional key path selector, e.g. spring.datasource, $.servers[0].host, routes[*].id",
+        flatten = "If true, return `dotted.key = value` lines instead of JSON",
+        format = "Content format override: yaml, properties, json or xml; detected from data_id by default"
+    )
 )]
-pub async fn get_nacos_config_by_client(namespace: String, data_id: String, group: Option<String>) -> Result<ToolResponseContent> {
+pub async fn get_nacos_config_by_client(
+    namespace: String,
+    data_id: String,
+    group: Option<String>,
+    cluster: Option<String>,
+    path: Option<String>,
+    flatten: Option<bool>,
+    format: Option<String>,
+) -> Result<ToolResponseContent> {
     let group = group.unwrap_or_else(|| "DEFAULT_GROUP".to_string());
-    let config = get_nacos_config_by(&namespace, &data_id, &group).await?;
-    Ok(tool_text_content!(config))
+    let config = get_nacos_config_by(cluster.as_deref(), &namespace, &data_id, &group).await?;
+    let path = path.filter(|p| !p.trim().is_empty());
+    let flatten = flatten.unwrap_or(false);
+    if path.is_none() && !flatten {
+        return Ok(tool_text_content!(config));
+    }
+
+    let format = match format {
+        Some(name) => ConfigFormat::from_name(&name)
+            .ok_or_else(|| anyhow::anyhow!("Unsupported config format '{}'", name))?,
+        None => detect_format(&data_id, &config),
+    };
+    let parsed = parse_config(&config, format)
+        .map_err(|e| anyhow::anyhow!("Failed to parse {} as {}: {}", data_id, format.as_str(), e))?;
+    // 多文档 YAML 已合并，在输出前加一行说明
+    let note = merged_documents_note(&config, format).map(|note| format!("# Note: {}\n", note));
+    let selected = match &path {
+        Some(path) => {
+            let selected = select_path(&parsed, path)?;
+            if selected.is_empty() {
+                return Err(anyhow::anyhow!("Path '{}' not found in {}", path, data_id));
+            }
+            selected
+        }
+        None => vec![(String::new(), parsed)],
+    };
+
+    if flatten {
+        let lines: Vec<String> = selected
+            .iter()
+            .flat_map(|(prefix, value)| flatten_config_with_prefix(prefix, value))
+            .map(|(key, value)| match value {
+                Value::String(s) => format!("{} = {}", key, s),
+                other => format!("{} = {}", key, other),
+            })
+            .collect();
+        return Ok(tool_text_content!(format!("{}{}", note.unwrap_or_default(), lines.join("\n"))));
+    }
+    let output = if selected.len() == 1 {
+        selected.into_iter().next().map(|(_, value)| value).unwrap_or_default()
+    } else {
+        Value::Object(selected.into_iter().collect())
+    };
+    Ok(tool_text_content!(format!(
+        "{}{}",
+        note.unwrap_or_default(),
+        serde_json::to_string_pretty(&output)?
+    )))
 }
 
 #[tool(
     name = "GetNacosServiceInfoByClient",
     description = "Get Nacos registered service instance information for specified namespace and service name",
-    params(namespace = "Nacos namespace", service_name = "Nacos service name", group = "Nacos group, leave empty to get all groups")
+    params(namespace = "Nacos namespace", service_name = "Nacos service name", group = "Nacos group, leave empty to get all groups", cluster = "Nacos cluster name from ListNacosClusters, defaults to the default cluster")
 )]
-pub async fn get_nacos_service_info_by_client(namespace: String, service_name: String, group: Option<String>) -> Result<ToolResponseContent> {
-    let info = get_nacos_service_info_by(&namespace, &service_name, group).await?;
+pub async fn get_nacos_service_info_by_client(namespace: String, service_name: String, group: Option<String>, cluster: Option<String>) -> Result<ToolResponseContent> {
+    let info = get_nacos_service_info_by(cluster.as_deref(), &namespace, &service_name, group).await?;
     Ok(tool_text_content!(serde_json::to_string_pretty(&info)?))
 }
+
+#[tool(
+    name = "ListNacosClusters",
+    description = "List all available Nacos clusters (name, server address, auth mode and description). Use the name as the cluster parameter of other Nacos tools. Clusters marked [open API: unsupported] (AK/SK auth, e.g. Aliyun MSE) only work with GetNacosConfigByClient and GetNacosServiceInfoByClient"
+)]
+pub async fn list_nacos_clusters() -> Result<ToolResponseContent> {
+    let config = get_config_inner()?;
+    let mut clusters = config.nacos_clusters.clone();
+    if !clusters.iter().any(|c| c.name == DEFAULT_NACOS_CLUSTER) {
+        clusters.insert(0, default_nacos_cluster());
+    }
+    let lines: Vec<String> = clusters
+        .iter()
+        .map(|c| format!(
+            "{}: {} ({}) [auth: {}]{}",
+            c.name,
+            c.description,
+            c.server_addr,
+            nacos_auth_mode(c).as_str(),
+            if open_api_supported(c) { "" } else { " [open API: unsupported]" }
+        ))
+        .collect();
+    Ok(tool_text_content!(lines.join("\n")))
+}
+
+#[tool(
+    name = "ListNacosNamespaces",
+    description = "List all namespaces (id, name, config count) of a Nacos cluster via the Nacos open API",
+    params(cluster = "Nacos cluster name from ListNacosClusters, defaults to the default cluster")
+)]
+pub async fn list_nacos_namespaces(cluster: Option<String>) -> Result<ToolResponseContent> {
+    let api = NacosOpenApi::new(resolve_nacos_cluster(cluster.as_deref())?)?;
+    let namespaces = api.list_namespaces().await?;
+    Ok(tool_text_content!(serde_json::to_string_pretty(&namespaces)?))
+}
+
+/// 返回包含关键字的行，帮助定位配置项所在位置
+fn matched_lines(content: &str, keyword: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter(|line| line.contains(keyword))
+        .take(MAX_MATCHED_LINES)
+        .map(|line| line.trim().to_string())
+        .collect()
+}
+
+#[tool(
+    name = "SearchNacosConfigs",
+    description = "List or search configs in a Nacos namespace. data_id and group support * globs (e.g. *user*), keyword searches the config content. Returns data_id, group, type and md5 of each match (plus matching lines when keyword is given), use GetNacosConfigByClient to fetch the full content",
+    params(
+        namespace = "Nacos namespace id",
+        data_id = "data_id glob, defaults to all",
+        group = "group glob, defaults to all",
+        keyword = "Keyword to search for in config content",
+        page_no = "Page number starting from 1, defaults to 1",
+        page_size = "Page size, defaults to 20, max 100",
+        cluster = "Nacos cluster name from ListNacosClusters, defaults to the default cluster"
+    )
+)]
+pub async fn search_nacos_configs(
+    namespace: String,
+    data_id: Option<String>,
+    group: Option<String>,
+    keyword: Option<String>,
+    page_no: Option<u32>,
+    page_size: Option<u32>,
+    cluster: Option<String>,
+) -> Result<ToolResponseContent> {
+    let api = NacosOpenApi::new(resolve_nacos_cluster(cluster.as_deref())?)?;
+    let page = api
+        .search_configs(
+            &namespace,
+            data_id.as_deref().unwrap_or(""),
+            group.as_deref().unwrap_or(""),
+            keyword.as_deref(),
+            page_no.unwrap_or(1).max(1),
+            page_size.unwrap_or(20).clamp(1, MAX_PAGE_SIZE),
+        )
+        .await?;
+
+    let items: Vec<Value> = page["pageItems"]
+        .as_array()
+        .map(|items| items.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .map(|item| {
+            let mut summary = json!({
+                "data_id": item["dataId"],
+                "group": item["group"],
+                "type": item["type"],
+                "app_name": item["appName"],
+                "md5": item["md5"],
+            });
+            if let (Some(keyword), Some(content)) = (keyword.as_deref(), item["content"].as_str()) {
+                summary["matched_lines"] = json!(matched_lines(content, keyword));
+            }
+            summary
+        })
+        .collect();
+
+    let result = json!({
+        "total_count": page["totalCount"],
+        "page_number": page["pageNumber"],
+        "pages_available": page["pagesAvailable"],
+        "items": items,
+    });
+    Ok(tool_text_content!(serde_json::to_string_pretty(&result)?))
+}
+
+#[tool(
+    name = "GetNacosConfigHistory",
+    description = "List the change history (revisions) of a Nacos config, newest first. Each revision has an id, operation type, operator and modification time; use GetNacosConfigRevision with the id to fetch that revision's content",
+    params(
+        namespace = "Nacos namespace id",
+        data_id = "Nacos data id",
+        group = "Nacos group, defaults to DEFAULT_GROUP",
+        page_no = "Page number starting from 1, defaults to 1",
+        page_size = "Page size, defaults to 20, max 100",
+        cluster = "Nacos cluster name from ListNacosClusters, defaults to the default cluster"
+    )
+)]
+pub async fn get_nacos_config_history(
+    namespace: String,
+    data_id: String,
+    group: Option<String>,
+    page_no: Option<u32>,
+    page_size: Option<u32>,
+    cluster: Option<String>,
+) -> Result<ToolResponseContent> {
+    let group = group.unwrap_or_else(|| "DEFAULT_GROUP".to_string());
+    let api = NacosOpenApi::new(resolve_nacos_cluster(cluster.as_deref())?)?;
+    let history = api
+        .config_history(
+            &namespace,
+            &data_id,
+            &group,
+            page_no.unwrap_or(1).max(1),
+            page_size.unwrap_or(20).clamp(1, MAX_PAGE_SIZE),
+        )
+        .await?;
+    Ok(tool_text_content!(serde_json::to_string_pretty(&history)?))
+}
+
+#[tool(
+    name = "GetNacosConfigRevision",
+    description = "Get the full content of one historical revision of a Nacos config, by the revision id returned from GetNacosConfigHistory",
+    params(
+        namespace = "Nacos namespace id",
+        data_id = "Nacos data id",
+        group = "Nacos group, defaults to DEFAULT_GROUP",
+        revision_id = "Revision id (the id field from GetNacosConfigHistory)",
+        cluster = "Nacos cluster name from ListNacosClusters, defaults to the default cluster"
+    )
+)]
+pub async fn get_nacos_config_revision(
+    namespace: String,
+    data_id: String,
+    group: Option<String>,
+    revision_id: String,
+    cluster: Option<String>,
+) -> Result<ToolResponseContent> {
+    let group = group.unwrap_or_else(|| "DEFAULT_GROUP".to_string());
+    let api = NacosOpenApi::new(resolve_nacos_cluster(cluster.as_deref())?)?;
+    let revision = api
+        .config_revision(&namespace, &data_id, &group, &revision_id)
+        .await?;
+    Ok(tool_text_content!(serde_json::to_string_pretty(&revision)?))
+}
+
+/// 在 nacos 配置中按 namespace（或其描述，如"测试环境"）查找应用对应的 data_id
+fn find_application_data_id<'a>(
+    config: &'a AppConfig,
+    namespace: &str,
+    application_name: &str,
+) -> Result<(&'a NacosConfig, &'a NacosDataId)> {
+    let nacos = config
+        .nacos
+        .iter()
+        .find(|n| n.namespace == namespace || n.description == namespace)
+        .ok_or_else(|| anyhow::anyhow!("Nacos namespace '{}' not found in configuration", namespace))?;
+    let data_id = nacos
+        .data_ids
+        .iter()
+        .find(|d| d.application_name == application_name)
+        .ok_or_else(|| anyhow::anyhow!(
+            "Application '{}' has no data_id configured in namespace '{}'",
+            application_name,
+            nacos.namespace
+        ))?;
+    Ok((nacos, data_id))
+}
+
+/// 返回解析结果，以及多文档 YAML 被合并时的说明
+async fn fetch_parsed_config(nacos: &NacosConfig, data_id: &NacosDataId) -> Result<(Value, Option<String>)> {
+    let content = get_nacos_config_by(
+        nacos.cluster.as_deref(),
+        &nacos.namespace,
+        &data_id.data_id,
+        &data_id.group,
+    )
+    .await?;
+    let format = detect_format(&data_id.data_id, &content);
+    let value = parse_config(&content, format).map_err(|e| {
+        anyhow::anyhow!(
+            "Failed to parse {} ({}) in namespace '{}' as {}: {}",
+            data_id.data_id,
+            data_id.group,
+            nacos.namespace,
+            format.as_str(),
+            e
+        )
+    })?;
+    let note = merged_documents_note(&content, format)
+        .map(|note| format!("{} in namespace '{}': {}", data_id.data_id, nacos.namespace, note));
+    Ok((value, note))
+}
+
+#[tool(
+    name = "DiffNacosConfig",
+    description = "Compare the same application's Nacos config between two namespaces (e.g. test vs prod), as configured in the nacos section of GetNacosConfig. Parses YAML, properties or JSON and returns a key-level diff (changed keys, keys only in one side) with secret values masked. Merged multi-document YAML is reported in notes",
+    params(
+        application_name = "Application name as configured in GetNacosConfig",
+        from_namespace = "Namespace id or its description (e.g. 测试环境) to compare from",
+        to_namespace = "Namespace id or its description to compare to"
+    )
+)]
+pub async fn diff_nacos_config(application_name: String, from_namespace: String, to_namespace: String) -> Result<ToolResponseContent> {
+    let config = get_config_inner()?;
+    let (from_nacos, from_data_id) = find_application_data_id(&config, &from_namespace, &application_name)?;
+    let (to_nacos, to_data_id) = find_application_data_id(&config, &to_namespace, &application_name)?;
+
+    let ((from_value, from_note), (to_value, to_note)) = tokio::try_join!(
+        fetch_parsed_config(from_nacos, from_data_id),
+        fetch_parsed_config(to_nacos, to_data_id),
+    )?;
+    let diff = diff_configs(&from_value, &to_value);
+
+    let mut result = json!({
+        "application_name": application_name,
+        "from": { "namespace": from_nacos.namespace, "data_id": from_data_id.data_id, "group": from_data_id.group },
+        "to": { "namespace": to_nacos.namespace, "data_id": to_data_id.data_id, "group": to_data_id.group },
+        "summary": {
+            "changed": diff.changed.len(),
+            "only_in_from": diff.only_in_from.len(),
+            "only_in_to": diff.only_in_to.len(),
+            "unchanged": diff.unchanged_count,
+        },
+        "diff": diff,
+    });
+    let notes: Vec<String> = from_note.into_iter().chain(to_note).collect();
+    if !notes.is_empty() {
+        result["notes"] = json!(notes);
+    }
+    Ok(tool_text_content!(serde_json::to_string_pretty(&result)?))
+}
+
+#[tool(
+    name = "ListNacosServices",
+    description = "List registered services in a Nacos namespace and group, with instance, healthy and unhealthy counts and instance clusters for each service",
+    params(
+        namespace = "Nacos namespace id",
+        group = "Nacos service group, defaults to DEFAULT_GROUP",
+        page_no = "Page number starting from 1, defaults to 1",
+        page_size = "Page size, defaults to 20, max 100",
+        cluster = "Nacos cluster name from ListNacosClusters, defaults to the default cluster"
+    )
+)]
+pub async fn list_nacos_services(
+    namespace: String,
+    group: Option<String>,
+    page_no: Option<u32>,
+    page_size: Option<u32>,
+    cluster: Option<String>,
+) -> Result<ToolResponseContent> {
+    let naming = get_naming_service(&resolve_nacos_cluster(cluster.as_deref())?, &namespace)?;
+    let page_no = page_no.unwrap_or(1).max(1);
+    let page_size = page_size.unwrap_or(20).clamp(1, MAX_PAGE_SIZE);
+    let (service_names, total) = naming
+        .get_service_list(page_no as i32, page_size as i32, group.clone())
+        .await?;
+    let services = summarize_services(&naming, group, service_names).await?;
+    let result = json!({
+        "total_count": total,
+        "page_number": page_no,
+        "services": services,
+    });
+    Ok(tool_text_content!(serde_json::to_string_pretty(&result)?))
+}
+
+#[tool(
+    name = "GetNacosServiceInstances",
+    description = "Get instances of a Nacos service grouped by instance cluster, with ip, port, weight, healthy/enabled flags and metadata. Optionally filter to healthy or unhealthy instances and specific instance clusters",
+    params(
+        namespace = "Nacos namespace id",
+        service_name = "Nacos service name",
+        group = "Nacos service group, defaults to DEFAULT_GROUP",
+        healthy = "true to return only healthy (enabled and healthy) instances, false for unhealthy or disabled ones, omit for all",
+        instance_clusters = "Comma-separated instance cluster names to include, omit for all",
+        cluster = "Nacos cluster name from ListNacosClusters, defaults to the default cluster"
+    )
+)]
+pub async fn get_nacos_service_instances(
+    namespace: String,
+    service_name: String,
+    group: Option<String>,
+    healthy: Option<bool>,
+    instance_clusters: Option<String>,
+    cluster: Option<String>,
+) -> Result<ToolResponseContent> {
+    let naming = get_naming_service(&resolve_nacos_cluster(cluster.as_deref())?, &namespace)?;
+    let clusters: Vec<String> = instance_clusters
+        .unwrap_or_default()
+        .split(',')
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect();
+    let instances = naming
+        .get_all_instances(service_name.clone(), group, clusters, false)
+        .await?;
+    let instances = instances
+        .into_iter()
+        // 与 ServiceHealthSummary 一致：仅 enabled 且 healthy 的实例算健康
+        .filter(|i| healthy.is_none_or(|h| (i.enabled && i.healthy) == h))
+        .collect();
+    let result = json!({
+        "service_name": service_name,
+        "clusters": group_instances_by_cluster(instances),
+    });
+    Ok(tool_text_content!(serde_json::to_string_pretty(&result)?))
+}
+
+#[tool(
+    name = "ListUnhealthyNacosServices",
+    description = "On-call summary: scan all services in a Nacos namespace and group and report services with zero healthy instances, plus services that are only partially healthy",
+    params(
+        namespace = "Nacos namespace id",
+        group = "Nacos service group, defaults to DEFAULT_GROUP",
+        cluster = "Nacos cluster name from ListNacosClusters, defaults to the default cluster"
+    )
+)]
+pub async fn list_unhealthy_nacos_services(namespace: String, group: Option<String>, cluster: Option<String>) -> Result<ToolResponseContent> {
+    let naming = get_naming_service(&resolve_nacos_cluster(cluster.as_deref())?, &namespace)?;
+    let service_names = list_all_service_names(&naming, group.clone()).await?;
+    let total = service_names.len();
+    let summaries = summarize_services(&naming, group, service_names).await?;
+
+    let (no_healthy, partially_healthy): (Vec<_>, Vec<_>) = summaries
+        .into_iter()
+        .filter(|s| s.healthy_count < s.instance_count || s.instance_count == 0)
+        .partition(|s| s.healthy_count == 0);
+    let result = json!({
+        "total_services": total,
+        "no_healthy_instances": no_healthy,
+        "partially_healthy": partially_healthy,
+    });
+    Ok(tool_text_content!(serde_json::to_string_pretty(&result)?))
+}
+
+#[tool(
+    name = "ListNacosServiceSubscribers",
+    description = "List the clients (address, agent, app) subscribed to a Nacos service via the Nacos open API",
+    params(
+        namespace = "Nacos namespace id",
+        service_name = "Nacos service name",
+        group = "Nacos service group, defaults to DEFAULT_GROUP",
+        page_no = "Page number starting from 1, defaults to 1",
+        page_size = "Page size, defaults to 20, max 100",
+        cluster = "Nacos cluster name from ListNacosClusters, defaults to the default cluster"
+    )
+)]
+pub async fn list_nacos_service_subscribers(
+    namespace: String,
+    service_name: String,
+    group: Option<String>,
+    page_no: Option<u32>,
+    page_size: Option<u32>,
+    cluster: Option<String>,
+) -> Result<ToolResponseContent> {
+    let group = group.unwrap_or_else(|| "DEFAULT_GROUP".to_string());
+    let api = NacosOpenApi::new(resolve_nacos_cluster(cluster.as_deref())?)?;
+    let subscribers = api
+        .service_subscribers(
+            &namespace,
+            &service_name,
+            &group,
+            page_no.unwrap_or(1).max(1),
+            page_size.unwrap_or(20).clamp(1, MAX_PAGE_SIZE),
+        )
+        .await?;
+    Ok(tool_text_content!(serde_json::to_string_pretty(&subscribers)?))
+}
+
+#[tool(
+    name = "PublishNacosConfig",
+    description = "Publish a Nacos config in two steps. Call without change_token for a dry run: returns a unified diff against the current content and a change token (valid 10 minutes). Review the diff, then call again with exactly the same arguments plus change_token to apply. The publish fails if the config was modified in between (MD5 check). Only namespaces listed in the nacos section of GetNacosConfig for the same cluster and with an environment can be published to; production namespaces are rejected unless the server allows it. Every publish is written to an audit log",
+    params(
+        namespace = "Nacos namespace id",
+        data_id = "Nacos data id",
+        group = "Nacos group, defaults to DEFAULT_GROUP",
+        content = "The complete new config content",
+        content_type = "Config type such as yaml, properties, json, text; defaults to the server default",
+        change_token = "Change token returned by the dry run; omit to perform a dry run",
+        cluster = "Nacos cluster name from ListNacosClusters, defaults to the default cluster"
+    )
+)]
+pub async fn publish_nacos_config(
+    namespace: String,
+    data_id: String,
+    group: Option<String>,
+    content: String,
+    content_type: Option<String>,
+    change_token: Option<String>,
+    cluster: Option<String>,
+) -> Result<ToolResponseContent> {
+    let group = group.unwrap_or_else(|| "DEFAULT_GROUP".to_string());
+    match change_token.filter(|t| !t.is_empty()) {
+        None => {
+            let preview = preview_publish(cluster.as_deref(), &namespace, &data_id, &group, &content, content_type).await?;
+            Ok(tool_text_content!(serde_json::to_string_pretty(&preview)?))
+        }
+        Some(token) => {
+            let message = apply_publish(&token, cluster.as_deref(), &namespace, &data_id, &group, &content, content_type).await?;
+            Ok(tool_text_content!(message))
+        }
+    }
+}
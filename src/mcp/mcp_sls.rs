@@ -0,0 +1,187 @@
+// 基于原生 SLS 客户端的日志查询工具
+use anyhow::Result;
+use mcp_core::tool_text_content;
+use mcp_core::types::ToolResponseContent;
+use mcp_core_macros::tool;
+use crate::utils::nacos_config::get_config_inner;
+use crate::utils::sls_client::{GetLogsRequest, SlsClient};
+use crate::utils::sls_query::{build_query, parse_log_record, resolve_logstore, resolve_time_window};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::env;
+use std::time::Duration;
+
+/// 单次 GetLogs 最多返回的行数（SLS 限制为 100）
+pub const MAX_LOG_LINES: u32 = 100;
+
+/// 结果为 Incomplete 时的重试次数
+pub fn sls_incomplete_retries() -> u32 {
+    env::var("SLS_INCOMPLETE_RETRIES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(2)
+}
+
+const INCOMPLETE_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// 按 project 名创建客户端，config 中为 project 配置了 endpoint 时优先使用
+pub fn sls_client_for_project(project: &str) -> Result<SlsClient> {
+    let endpoint = get_config_inner().ok().and_then(|config| {
+        config
+            .sls
+            .projects
+            .iter()
+            .find(|p| p.name == project)
+            .and_then(|p| p.endpoint.clone())
+    });
+    SlsClient::from_env(endpoint.as_deref())
+}
+
+#[tool(
+    name = "SlsGetLogs",
+    description = "Query logs from an Aliyun SLS logstore with the native SLS API (GetLogs). Returns JSON with progress (Complete/Incomplete), count and the log records. Use GetSLSConfig for available projects and logstores, and GetCurrentTime for the current time",
+    params(
+        project = "SLS project name",
+        logstore = "SLS logstore name",
+        from = "Start time, Unix timestamp in seconds",
+        to = "End time, Unix timestamp in seconds",
+        query = "SLS query / analytic statement, defaults to * (all logs)",
+        line = "Max number of logs to return, defaults to 20, max 100",
+        offset = "Offset of the first log to return (newest first), defaults to 0"
+    )
+)]
+pub async fn sls_get_logs(
+    project: String,
+    logstore: String,
+    from: i64,
+    to: i64,
+    query: Option<String>,
+    line: Option<u32>,
+    offset: Option<u32>,
+) -> Result<ToolResponseContent> {
+    let client = sls_client_for_project(&project)?;
+    let request = GetLogsRequest {
+        from,
+        to,
+        query: query.unwrap_or_else(|| "*".to_string()),
+        topic: None,
+        line: line.unwrap_or(20).clamp(1, MAX_LOG_LINES),
+        offset: offset.unwrap_or(0),
+        reverse: true,
+    };
+    let response = client.get_logs(&project, &logstore, &request).await?;
+    Ok(tool_text_content!(serde_json::to_string_pretty(&response)?))
+}
+
+#[tool(
+    name = "SlsGetHistograms",
+    description = "Get the log count distribution over time for an Aliyun SLS logstore and query (GetHistograms), useful to find when errors spiked before fetching logs",
+    params(
+        project = "SLS project name",
+        logstore = "SLS logstore name",
+        from = "Start time, Unix timestamp in seconds",
+        to = "End time, Unix timestamp in seconds",
+        query = "SLS query, defaults to * (all logs)"
+    )
+)]
+pub async fn sls_get_histograms(
+    project: String,
+    logstore: String,
+    from: i64,
+    to: i64,
+    query: Option<String>,
+) -> Result<ToolResponseContent> {
+    let client = sls_client_for_project(&project)?;
+    let query = query.unwrap_or_else(|| "*".to_string());
+    let response = client
+        .get_histograms(&project, &logstore, from, to, &query, None)
+        .await?;
+    Ok(tool_text_content!(serde_json::to_string_pretty(&response)?))
+}
+
+#[tool(
+    name = "SlsListLogstores",
+    description = "List logstores of an Aliyun SLS project with the native SLS API (ListLogStore)",
+    params(
+        project = "SLS project name",
+        name = "Optional logstore name filter (fuzzy match)",
+        offset = "Offset, defaults to 0",
+        size = "Page size, defaults to 100, max 500"
+    )
+)]
+pub async fn sls_list_logstores(
+    project: String,
+    name: Option<String>,
+    offset: Option<u32>,
+    size: Option<u32>,
+) -> Result<ToolResponseContent> {
+    let client = sls_client_for_project(&project)?;
+    let logstores = client
+        .list_logstores(&project, name.as_deref(), offset.unwrap_or(0), size.unwrap_or(100).clamp(1, 500))
+        .await?;
+    Ok(tool_text_content!(serde_json::to_string_pretty(&logstores)?))
+}
+
+#[tool(
+    name = "QuerySlsLogs",
+    description = "Query logs of an SLS logstore configured in GetSLSConfig. Project and logstore must be configured, filter fields must be indexed fields of the logstore. Returns JSON with the resolved time window, the final query and parsed log records (newest first). Incomplete results are retried; if still incomplete, incomplete=true and the records may be partial",
+    params(
+        project = "SLS project name from GetSLSConfig",
+        logstore = "Logstore name of the project from GetSLSConfig",
+        query = "SLS search / analytic statement, defaults to *",
+        time_range = "Time expression as accepted by ResolveTimeRange, e.g. 15m (default), 'last 1 hour', 'yesterday 09:00 Asia/Shanghai' or '2024-06-01 10:00:00~2024-06-01 11:00:00'",
+        filters = "Exact match filters on indexed fields, e.g. {\"level\":\"ERROR\",\"app\":\"order\"}",
+        limit = "Max number of records to return, defaults to 20, max 100"
+    )
+)]
+pub async fn query_sls_logs(
+    project: String,
+    logstore: String,
+    query: Option<String>,
+    time_range: Option<String>,
+    filters: Option<BTreeMap<String, String>>,
+    limit: Option<u32>,
+) -> Result<ToolResponseContent> {
+    let config = get_config_inner()?;
+    let (sls_project, sls_logstore) = resolve_logstore(&config.sls, &project, &logstore)?;
+    let (from, to) = resolve_time_window(time_range.as_deref())?;
+    let query = build_query(sls_logstore, query.as_deref(), &filters.unwrap_or_default())?;
+
+    let client = SlsClient::from_env(sls_project.endpoint.as_deref())?;
+    let request = GetLogsRequest {
+        from,
+        to,
+        query: query.clone(),
+        topic: None,
+        line: limit.unwrap_or(20).clamp(1, MAX_LOG_LINES),
+        offset: 0,
+        reverse: true,
+    };
+    // SLS 返回 Incomplete 时结果不完整，官方建议重试
+    let mut response = client.get_logs(&project, &logstore, &request).await?;
+    for _ in 0..sls_incomplete_retries() {
+        if response.is_complete() {
+            break;
+        }
+        tokio::time::sleep(INCOMPLETE_RETRY_DELAY).await;
+        response = client.get_logs(&project, &logstore, &request).await?;
+    }
+    let incomplete = !response.is_complete();
+    let logs: Vec<Value> = response.logs.into_iter().map(parse_log_record).collect();
+    let mut result = json!({
+        "project": project,
+        "logstore": logstore,
+        "environment": sls_project.environment,
+        "from": from,
+        "to": to,
+        "query": query,
+        "progress": response.progress,
+        "incomplete": incomplete,
+        "count": logs.len(),
+        "logs": logs,
+    });
+    if incomplete {
+        result["warning"] = json!("SLS reported the result as Incomplete after retries, records may be partial; narrow time_range or retry later");
+    }
+    Ok(tool_text_content!(serde_json::to_string_pretty(&result)?))
+}
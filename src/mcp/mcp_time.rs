@@ -1,14 +1,59 @@
 use anyhow::Result;
+use chrono::Utc;
 use mcp_core::tool_text_content;
 use mcp_core::types::ToolResponseContent;
 use mcp_core_macros::tool;
-use crate::utils::date_util::now_datetime_string;
+use serde_json::json;
+use crate::utils::date_util::{now_datetime_string, parse_time, resolve_time_range as resolve_range, resolve_time_zone, TimeFormats};
 
 #[tool(
     name = "GetCurrentTime",
-    description = "Get current server time in YYYY-MM-DD HH:MM:SS format, no parameters required",
+    description = "Get current server time. Returns JSON with epoch_seconds (for SLS), epoch_millis, rfc3339_utc, rfc3339 with offset (for Elasticsearch), datetime (YYYY-MM-DD HH:MM:SS) in the requested time zone, and the server local time",
+    params(time_zone = "Optional IANA time zone such as Asia/Shanghai or UTC, defaults to TIME_ZONE (Asia/Shanghai)")
 )]
-pub async fn get_current_time() -> Result<ToolResponseContent> {
-    let now = now_datetime_string();
-    Ok(tool_text_content!(now))
+pub async fn get_current_time(time_zone: Option<String>) -> Result<ToolResponseContent> {
+    let tz = resolve_time_zone(time_zone.as_deref())?;
+    let mut result = serde_json::to_value(TimeFormats::new(Utc::now(), tz))?;
+    result["server_local"] = json!(now_datetime_string());
+    Ok(tool_text_content!(serde_json::to_string_pretty(&result)?))
+}
+
+#[tool(
+    name = "ConvertTime",
+    description = "Convert a time between formats and time zones. Accepts epoch seconds, epoch milliseconds, RFC3339, or YYYY-MM-DD[ HH:MM[:SS]] interpreted in from_time_zone. Returns the same JSON formats as GetCurrentTime in to_time_zone",
+    params(
+        time = "Time to convert, e.g. 1718000000, 1718000000000, 2024-06-10T06:13:20Z or 2024-06-10 14:13:20",
+        from_time_zone = "IANA time zone used for times without offset, defaults to TIME_ZONE",
+        to_time_zone = "IANA time zone of the output, defaults to TIME_ZONE"
+    )
+)]
+pub async fn convert_time(
+    time: String,
+    from_time_zone: Option<String>,
+    to_time_zone: Option<String>,
+) -> Result<ToolResponseContent> {
+    let from_tz = resolve_time_zone(from_time_zone.as_deref())?;
+    let to_tz = resolve_time_zone(to_time_zone.as_deref())?;
+    let parsed = parse_time(&time, from_tz)?;
+    Ok(tool_text_content!(serde_json::to_string_pretty(&TimeFormats::new(parsed, to_tz))?))
+}
+
+#[tool(
+    name = "ResolveTimeRange",
+    description = "Resolve a time expression into a from/to pair for log and search queries. Supports 'last 15 minutes', 'past 2h', '15m', 'today', 'yesterday', 'yesterday 09:00' (from then until now), '2 hours ago', absolute times, and ranges 'A to B' / 'A ~ B'. A trailing IANA zone such as 'yesterday 09:00 Asia/Shanghai' overrides the time zone. Returns from and to in epoch seconds, epoch millis and RFC3339",
+    params(
+        expression = "Time expression, e.g. 'last 15 minutes' or 'yesterday 09:00 to yesterday 10:00 Asia/Shanghai'",
+        time_zone = "IANA time zone for expressions without one, defaults to TIME_ZONE"
+    )
+)]
+pub async fn resolve_time_range(expression: String, time_zone: Option<String>) -> Result<ToolResponseContent> {
+    let tz = resolve_time_zone(time_zone.as_deref())?;
+    let (from, to, tz) = resolve_range(&expression, tz, Utc::now())?;
+    let result = json!({
+        "expression": expression,
+        "from": TimeFormats::new(from, tz),
+        "to": TimeFormats::new(to, tz),
+        "duration_seconds": (to - from).num_seconds(),
+    });
+    Ok(tool_text_content!(serde_json::to_string_pretty(&result)?))
 }
@@ -1,5 +1,6 @@
 pub mod mcp_aliyun_cli;
 pub mod mcp_aliyun_log_cli;
+pub mod mcp_sls;
 pub mod mcp_time;
 pub mod mcp_config;
 pub mod mcp_nacos;
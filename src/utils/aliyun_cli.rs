@@ -0,0 +1,321 @@
+// aliyun CLI 调用策略：按 product / API 白名单放行，参数按 shell 规则拆分但不经过 shell 执行
+use crate::utils::cli_runner::CliOutput;
+use crate::utils::config_format::select_path;
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::env;
+use wildmatch::WildMatch;
+
+/// 默认只放行只读接口：RPC 风格的 Describe* / List* / Get*，以及 ROA 风格的 GET 请求
+pub const DEFAULT_ALLOWED_APIS: &str = "*:Describe*,*:List*,*:Get*,*:GET";
+
+/// 白名单，逗号分隔的 product:API 规则，支持 * 通配，如 ecs:Describe*,sls:GetLogs
+pub fn aliyun_cli_allowed_apis() -> String {
+    env::var("ALIYUN_CLI_ALLOWED_APIS").unwrap_or_else(|_| DEFAULT_ALLOWED_APIS.to_string())
+}
+
+/// aliyunlog 默认只放行只读子命令
+pub const DEFAULT_ALLOWED_LOG_COMMANDS: &str = "get_*,list_*";
+
+/// aliyunlog 白名单，逗号分隔的 log 子命令规则，支持 * 通配，如 get_logs,list_logstore
+pub fn aliyun_log_cli_allowed_commands() -> String {
+    env::var("ALIYUN_LOG_CLI_ALLOWED_COMMANDS").unwrap_or_else(|_| DEFAULT_ALLOWED_LOG_COMMANDS.to_string())
+}
+
+/// 单条 product:API 白名单规则，product 不区分大小写，API 区分大小写
+#[derive(Debug, Clone)]
+pub struct AllowedApi {
+    pub product: String,
+    pub api: String,
+}
+
+impl AllowedApi {
+    pub fn matches(&self, product: &str, api: &str) -> bool {
+        WildMatch::new(&self.product.to_lowercase()).matches(&product.to_lowercase())
+            && WildMatch::new(&self.api).matches(api)
+    }
+}
+
+impl std::fmt::Display for AllowedApi {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.product, self.api)
+    }
+}
+
+pub fn parse_allowed_apis(rules: &str) -> Vec<AllowedApi> {
+    rules
+        .split(',')
+        .map(str::trim)
+        .filter(|rule| !rule.is_empty())
+        .map(|rule| match rule.split_once(':') {
+            Some((product, api)) => AllowedApi {
+                product: product.trim().to_string(),
+                api: api.trim().to_string(),
+            },
+            None => AllowedApi {
+                product: rule.to_string(),
+                api: "*".to_string(),
+            },
+        })
+        .collect()
+}
+
+/// 按 shell 引号规则拆分命令行，支持带空格或 JSON 的参数，不做变量展开与命令替换
+pub fn split_command_line(command: &str) -> Result<Vec<String>> {
+    let args = shlex::split(command)
+        .ok_or_else(|| anyhow::anyhow!("Invalid command line, check unbalanced quotes or trailing escape: {}", command))?;
+    if args.is_empty() {
+        return Err(anyhow::anyhow!("Empty command"));
+    }
+    Ok(args)
+}
+
+/// 拆分 aliyun CLI 命令并校验 product / API 是否在白名单内，返回可直接传给进程的参数列表
+pub fn parse_aliyun_cli_command(command: &str) -> Result<Vec<String>> {
+    let args = split_command_line(command)?;
+    let (product, api) = match (args.first(), args.get(1)) {
+        (Some(product), Some(api)) if !product.starts_with('-') && !api.starts_with('-') => (product, api),
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Command must start with '<product> <API>', e.g. 'ecs DescribeInstances --RegionId cn-beijing'"
+            ))
+        }
+    };
+
+    let allowed = parse_allowed_apis(&aliyun_cli_allowed_apis());
+    if !allowed.iter().any(|rule| rule.matches(product, api)) {
+        let rules: Vec<String> = allowed.iter().map(|rule| rule.to_string()).collect();
+        return Err(anyhow::anyhow!(
+            "aliyun CLI call '{} {}' is not allowed, allowed product:API patterns: {}",
+            product,
+            api,
+            if rules.is_empty() { "(none)".to_string() } else { rules.join(", ") }
+        ));
+    }
+    Ok(args)
+}
+
+/// 拆分 aliyunlog 命令并校验 log 子命令是否在白名单内
+pub fn parse_aliyun_log_cli_command(command: &str) -> Result<Vec<String>> {
+    let args = split_command_line(command)?;
+    let subcommand = match (args.first().map(String::as_str), args.get(1)) {
+        (Some("log"), Some(subcommand)) if !subcommand.starts_with('-') => subcommand,
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Command must start with 'log <subcommand>', e.g. 'log get_logs --project=xxx --logstore=xxx ...'"
+            ))
+        }
+    };
+
+    let rules = aliyun_log_cli_allowed_commands();
+    let allowed: Vec<&str> = rules.split(',').map(str::trim).filter(|rule| !rule.is_empty()).collect();
+    if !allowed.iter().any(|rule| WildMatch::new(rule).matches(subcommand)) {
+        return Err(anyhow::anyhow!(
+            "aliyunlog subcommand '{}' is not allowed, allowed patterns: {}",
+            subcommand,
+            if allowed.is_empty() { "(none)".to_string() } else { allowed.join(", ") }
+        ));
+    }
+    Ok(args)
+}
+
+/// 类 aliyun CLI --output 的投影：cols=InstanceId,Status.Code rows=Instances.Instance[]
+#[derive(Debug, Clone, Default)]
+pub struct OutputProjection {
+    pub cols: Vec<String>,
+    pub rows: Option<String>,
+}
+
+impl OutputProjection {
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut projection = OutputProjection::default();
+        for part in spec.split_whitespace() {
+            match part.split_once('=') {
+                Some(("cols", cols)) => {
+                    projection.cols = cols
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|c| !c.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                }
+                Some(("rows", rows)) => projection.rows = Some(rows.trim().to_string()),
+                _ => {
+                    return Err(anyhow::anyhow!(
+                        "Invalid output spec '{}', expected 'cols=A,B.C rows=Path.To.List[]'",
+                        spec
+                    ))
+                }
+            }
+        }
+        if projection.cols.is_empty() {
+            return Err(anyhow::anyhow!("Output spec '{}' has no cols", spec));
+        }
+        Ok(projection)
+    }
+
+    /// 按 rows 路径取出行，再按 cols 路径取出列，返回对象数组
+    pub fn apply(&self, value: &Value) -> Result<Value> {
+        let rows: Vec<Value> = match &self.rows {
+            Some(rows) => select_path(value, &rows.replace("[]", "[*]"))?
+                .into_iter()
+                .flat_map(|(_, row)| match row {
+                    Value::Array(items) => items,
+                    other => vec![other],
+                })
+                .collect(),
+            None => vec![value.clone()],
+        };
+        let mut projected = Vec::with_capacity(rows.len());
+        for row in &rows {
+            let mut record = Map::new();
+            for col in &self.cols {
+                let cell = select_path(row, col)?
+                    .into_iter()
+                    .next()
+                    .map(|(_, v)| v)
+                    .unwrap_or(Value::Null);
+                record.insert(col.clone(), cell);
+            }
+            projected.push(Value::Object(record));
+        }
+        Ok(Value::Array(projected))
+    }
+}
+
+/// 取出命令行中的 --output 参数（aliyun CLI 会据此输出表格），改由工具自身投影 JSON 结果
+/// 支持 --output cols=.. rows=.. 与 --output=cols=.. rows=.. 两种写法
+pub fn take_output_arg(args: &mut Vec<String>) -> Option<String> {
+    let position = args
+        .iter()
+        .position(|arg| arg == "--output" || arg.starts_with("--output="))?;
+    let arg = args.remove(position);
+    let mut spec: Vec<String> = arg
+        .strip_prefix("--output=")
+        .filter(|value| !value.is_empty())
+        .map(|value| vec![value.to_string()])
+        .unwrap_or_default();
+    while position < args.len() && !args[position].starts_with("--") {
+        spec.push(args.remove(position));
+    }
+    Some(spec.join(" "))
+}
+
+/// aliyun 接口调用失败时的结构化错误
+#[derive(Debug, Clone, Serialize)]
+pub struct AliyunCliError {
+    pub code: Option<String>,
+    pub message: String,
+    pub request_id: Option<String>,
+    pub exit_status: Option<i32>,
+}
+
+impl std::fmt::Display for AliyunCliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let json = serde_json::to_string_pretty(self).map_err(|_| std::fmt::Error)?;
+        write!(f, "{}", json)
+    }
+}
+
+impl std::error::Error for AliyunCliError {}
+
+impl AliyunCliError {
+    /// 从 CLI 输出中提取错误码与 RequestId，兼容 JSON 错误体与 "ErrorCode: xxx" 文本格式
+    pub fn from_output(output: &CliOutput) -> Self {
+        let mut error = AliyunCliError {
+            code: None,
+            message: String::new(),
+            request_id: None,
+            exit_status: output.status,
+        };
+        for text in [&output.stdout, &output.stderr] {
+            if let Some(body) = parse_json_body(text) {
+                let field = |names: &[&str]| {
+                    names
+                        .iter()
+                        .find_map(|name| body.get(*name).and_then(Value::as_str).map(str::to_string))
+                };
+                error.code = error.code.or_else(|| field(&["Code", "code", "errorCode"]));
+                error.request_id = error.request_id.or_else(|| field(&["RequestId", "requestId"]));
+                if error.message.is_empty() {
+                    error.message = field(&["Message", "message", "errorMessage"]).unwrap_or_default();
+                }
+            }
+            for line in text.lines() {
+                let Some((key, value)) = line.split_once(':') else {
+                    continue;
+                };
+                let value = value.trim().to_string();
+                match key.trim() {
+                    "ErrorCode" | "Code" if error.code.is_none() => error.code = Some(value),
+                    "RequestId" if error.request_id.is_none() => error.request_id = Some(value),
+                    "Message" if error.message.is_empty() => error.message = value,
+                    _ => {}
+                }
+            }
+        }
+        if error.message.is_empty() {
+            error.message = [output.stderr.trim(), output.stdout.trim()]
+                .iter()
+                .filter(|s| !s.is_empty())
+                .cloned()
+                .collect::<Vec<_>>()
+                .join("\n");
+        }
+        error
+    }
+}
+
+/// 解析 CLI 输出中的 JSON，容忍前面的提示行（取第一个 { 或 [ 起的内容）
+pub fn parse_json_body(text: &str) -> Option<Value> {
+    let start = text.find(['{', '['])?;
+    serde_json::from_str(text[start..].trim()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aliyun_log_cli_allows_read_only_subcommands() {
+        let args = parse_aliyun_log_cli_command("log get_logs --project=p --logstore=l --query='a | b'").unwrap();
+        assert_eq!(args, ["log", "get_logs", "--project=p", "--logstore=l", "--query=a | b"]);
+        assert!(parse_aliyun_log_cli_command("log list_logstore --project_name=p").is_ok());
+        assert!(parse_aliyun_log_cli_command("log get_log_all --project=p").is_ok());
+    }
+
+    #[test]
+    fn aliyun_log_cli_rejects_writes_and_malformed_commands() {
+        let error = parse_aliyun_log_cli_command("log delete_logstore --project_name=p").unwrap_err();
+        assert!(error.to_string().contains("'delete_logstore' is not allowed"));
+        assert!(parse_aliyun_log_cli_command("log create_project --project_name=p").is_err());
+        assert!(parse_aliyun_log_cli_command("configure --access-id=x").is_err());
+        assert!(parse_aliyun_log_cli_command("log --help").is_err());
+    }
+
+    fn args(command: &str) -> Vec<String> {
+        split_command_line(command).unwrap()
+    }
+
+    #[test]
+    fn take_output_arg_supports_both_forms() {
+        let mut separated = args("ecs DescribeInstances --output cols=InstanceId,Status rows=Instances.Instance[] --RegionId cn-beijing");
+        assert_eq!(
+            take_output_arg(&mut separated).as_deref(),
+            Some("cols=InstanceId,Status rows=Instances.Instance[]")
+        );
+        assert_eq!(separated, ["ecs", "DescribeInstances", "--RegionId", "cn-beijing"]);
+
+        let mut quoted = args("ecs DescribeInstances --output='cols=InstanceId rows=Instances.Instance[]' --RegionId cn-beijing");
+        assert_eq!(take_output_arg(&mut quoted).as_deref(), Some("cols=InstanceId rows=Instances.Instance[]"));
+        assert_eq!(quoted, ["ecs", "DescribeInstances", "--RegionId", "cn-beijing"]);
+
+        let mut unquoted = args("ecs DescribeInstances --RegionId cn-beijing --output=cols=InstanceId rows=Instances.Instance[]");
+        assert_eq!(take_output_arg(&mut unquoted).as_deref(), Some("cols=InstanceId rows=Instances.Instance[]"));
+        assert_eq!(unquoted, ["ecs", "DescribeInstances", "--RegionId", "cn-beijing"]);
+
+        let mut none = args("ecs DescribeRegions");
+        assert_eq!(take_output_arg(&mut none), None);
+    }
+}
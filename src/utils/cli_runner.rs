@@ -0,0 +1,117 @@
+// 外部 CLI 异步执行：超时后杀掉进程、限制输出大小、限制并发数
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use std::env;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::Command;
+use tokio::sync::Semaphore;
+
+pub fn cli_timeout_secs() -> u64 {
+    env::var("CLI_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(60)
+}
+
+/// stdout / stderr 各自保留的最大字节数
+pub fn cli_max_output_bytes() -> usize {
+    env::var("CLI_MAX_OUTPUT_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(256 * 1024)
+}
+
+pub fn cli_max_concurrency() -> usize {
+    env::var("CLI_MAX_CONCURRENCY")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(4)
+}
+
+static CLI_PERMITS: Lazy<Semaphore> = Lazy::new(|| Semaphore::new(cli_max_concurrency()));
+
+#[derive(Debug, Clone)]
+pub struct CliOutput {
+    pub status: Option<i32>, // 退出码，被信号终止时为 None
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl CliOutput {
+    pub fn success(&self) -> bool {
+        self.status == Some(0)
+    }
+}
+
+/// 读取全部输出但只保留前 limit 字节，超出部分丢弃并追加截断标记
+async fn read_capped<R: AsyncRead + Unpin>(mut reader: R, limit: usize) -> std::io::Result<String> {
+    let mut kept = Vec::new();
+    let mut dropped = 0usize;
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        let room = limit.saturating_sub(kept.len());
+        kept.extend_from_slice(&buf[..n.min(room)]);
+        dropped += n.saturating_sub(room);
+    }
+    let mut text = String::from_utf8_lossy(&kept).into_owned();
+    if dropped > 0 {
+        text.push_str(&format!("\n...[truncated {} bytes]", dropped));
+    }
+    Ok(text)
+}
+
+/// 执行 CLI 并等待结束，超时则杀掉进程并返回错误
+pub async fn run_cli(program: &str, args: &[String]) -> Result<CliOutput> {
+    let _permit = CLI_PERMITS.acquire().await?;
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Failed to start {}: {}", program, e))?;
+
+    let limit = cli_max_output_bytes();
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let stderr = child.stderr.take().expect("stderr is piped");
+    let timeout = Duration::from_secs(cli_timeout_secs());
+    let finished = tokio::time::timeout(timeout, async {
+        let (stdout, stderr, status) = tokio::join!(
+            read_capped(stdout, limit),
+            read_capped(stderr, limit),
+            child.wait()
+        );
+        Ok::<_, std::io::Error>((stdout?, stderr?, status?))
+    })
+    .await;
+
+    match finished {
+        Ok(result) => {
+            let (stdout, stderr, status) = result?;
+            Ok(CliOutput {
+                status: status.code(),
+                stdout,
+                stderr,
+            })
+        }
+        Err(_) => {
+            if let Err(e) = child.kill().await {
+                eprintln!("[CLI] 结束超时进程 {} 失败: {e}", program);
+            }
+            Err(anyhow::anyhow!(
+                "{} timed out after {}s and was killed (set CLI_TIMEOUT_SECS to change the limit)",
+                program,
+                timeout.as_secs()
+            ))
+        }
+    }
+}
+
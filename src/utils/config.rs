@@ -21,6 +21,8 @@ pub struct SlsProject {
     pub name: String,         // project 名称
     pub environment: String,  // 环境说明（如 prod、test、dev）
     pub description: String,  // project 说明
+    #[serde(default)]
+    pub endpoint: Option<String>, // SLS 服务地址（可选），默认 SLS_ENDPOINT 或 {region}.log.aliyuncs.com
     pub logstores: Vec<SlsLogstore>, // 该 project 下的 logstore
 }
 
@@ -33,9 +35,24 @@ pub struct SlsConfig {
 pub struct NacosConfig {
     pub description: String, // nacos 配置说明
     pub namespace: String,
+    #[serde(default)]
+    pub cluster: Option<String>, // 所属 Nacos 集群名称，为空时使用默认集群
+    #[serde(default)]
+    pub environment: Option<String>, // 环境（如 "prod", "pre", "test"），prod 默认禁止发布配置
     pub data_ids: Vec<NacosDataId>,
 }
 
+#[derive(Debug, Deserialize, Clone, Serialize, JsonSchema, PartialEq)]
+pub struct NacosClusterConfig {
+    pub name: String,               // 集群名称，Nacos 工具通过该名称选择集群
+    pub server_addr: String,        // 服务地址，多个地址用逗号分隔
+    pub username: Option<String>,   // 用户名（可选，开启鉴权时使用）
+    pub password: Option<String>,   // 密码（可选）
+    pub access_key: Option<String>, // 阿里云 AK（可选，与用户名密码二选一）
+    pub secret_key: Option<String>, // 阿里云 SK（可选）
+    pub description: String,        // 集群描述
+}
+
 #[derive(Debug, Deserialize, Clone, Serialize, JsonSchema)]
 pub struct NacosDataId {
     pub data_id: String,
@@ -64,20 +81,41 @@ pub struct RedisConfig {
     pub environment: String, // 环境（如 "prod", "staging", "dev"）
 }
 
-#[derive(Debug, Deserialize, Clone, Serialize, JsonSchema)]
+#[derive(Debug, Deserialize, Clone, Serialize, JsonSchema, PartialEq)]
 pub struct ElasticsearchConfig {
     pub name: String,           // 连接名称
     pub url: String,            // Elasticsearch 服务器地址
-    pub version: String,        // 版本（如 "6.8", "8.0"）
+    #[serde(default)]
+    pub urls: Vec<String>,      // 多节点地址（可选），配置后替代 url 轮询使用
+    #[serde(default)]
+    pub sniff: bool,            // 是否定期通过 _nodes/http 嗅探集群节点
+    #[serde(default)]
+    pub version: Option<String>, // 版本（如 "6.8", "7.17", "8.0", "opensearch-2.11"），为空时通过根路径自动识别
     pub username: Option<String>, // 用户名（可选）
     pub password: Option<String>, // 密码（可选）
     pub description: String,    // 连接描述
     pub environment: String,    // 环境（如 "prod", "pre", "test"）
+    #[serde(default)]
+    pub timeout_secs: Option<u64>, // 请求超时（秒，可选），默认 ES_REQUEST_TIMEOUT_SECS
+    #[serde(default)]
+    pub api_key: Option<String>, // API Key（可选），"id:api_key" 或已 base64 编码的值，优先于用户名密码
+    #[serde(default)]
+    pub bearer_token: Option<String>, // Bearer Token（可选），优先于用户名密码
+    #[serde(default)]
+    pub ca_cert: Option<String>, // 自定义 CA 证书路径（PEM，可选）
+    #[serde(default)]
+    pub client_cert: Option<String>, // 客户端证书路径（PKCS#12，如 elasticsearch-certutil 生成的 .p12，可选）
+    #[serde(default)]
+    pub client_cert_password: Option<String>, // 客户端证书密码（可选）
+    #[serde(default)]
+    pub skip_tls_verify: bool, // 跳过 TLS 证书校验，仅用于测试环境
 }
 
 #[derive(Debug, Deserialize, Clone, Serialize, JsonSchema)]
 pub struct ElasticsearchField {
     pub name: String,           // 字段名称
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub field_type: Option<String>, // 字段类型（由 mapping 生成）
     pub description: String,    // 字段描述
     pub is_sortable: bool,      // 是否为排序字段
     pub is_aggregatable: bool,  // 是否为聚类字段
@@ -100,6 +138,8 @@ pub struct ElasticsearchFieldsConfig {
 pub struct AppConfig {
     pub sls: SlsConfig,
     pub nacos: Vec<NacosConfig>,
+    #[serde(default)]
+    pub nacos_clusters: Vec<NacosClusterConfig>, // Nacos 集群列表
     pub mysql: Vec<MySQLConfig>,  // 改为 Vec 以支持多个连接
     pub redis: Vec<RedisConfig>,  // Redis 连接配置
     pub elasticsearch: Vec<ElasticsearchConfig>, // Elasticsearch 连接配置
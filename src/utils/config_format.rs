@@ -0,0 +1,712 @@
+// 配置内容解析：把 YAML / properties / JSON / XML 统一解析为 JSON 结构，支持路径选择与展开为点分 key
+use anyhow::Result;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Yaml,
+    Properties,
+    Json,
+    Xml,
+}
+
+impl ConfigFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Yaml => "yaml",
+            Self::Properties => "properties",
+            Self::Json => "json",
+            Self::Xml => "xml",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "yaml" | "yml" => Some(Self::Yaml),
+            "properties" => Some(Self::Properties),
+            "json" => Some(Self::Json),
+            "xml" => Some(Self::Xml),
+            _ => None,
+        }
+    }
+}
+
+/// 根据 data_id 后缀推断格式，无法判断时根据内容猜测
+pub fn detect_format(data_id: &str, content: &str) -> ConfigFormat {
+    if let Some(format) = data_id.rsplit_once('.').and_then(|(_, ext)| ConfigFormat::from_name(ext)) {
+        return format;
+    }
+    let trimmed = content.trim_start();
+    if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        ConfigFormat::Json
+    } else if trimmed.starts_with('<') {
+        ConfigFormat::Xml
+    } else if content
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty() && !l.starts_with('#') && !l.starts_with('!'))
+        .is_some_and(|l| l.contains('=') && !l.contains(": "))
+    {
+        ConfigFormat::Properties
+    } else {
+        ConfigFormat::Yaml
+    }
+}
+
+pub fn parse_config(content: &str, format: ConfigFormat) -> Result<Value> {
+    match format {
+        ConfigFormat::Json => Ok(serde_json::from_str(content)?),
+        ConfigFormat::Yaml => parse_yaml(content),
+        ConfigFormat::Properties => Ok(parse_properties(content)),
+        ConfigFormat::Xml => parse_xml(content),
+    }
+}
+
+/// 解析 YAML，多文档（---）按顺序深度合并，后面的文档覆盖前面的
+fn parse_yaml(content: &str) -> Result<Value> {
+    let mut merged = Value::Null;
+    for document in serde_yaml::Deserializer::from_str(content) {
+        let value = serde_yaml::Value::deserialize(document)?;
+        merge_value(&mut merged, yaml_to_json(value));
+    }
+    Ok(merged)
+}
+
+/// 多文档 YAML 被合并时的说明，其他格式或单文档时为 None
+pub fn merged_documents_note(content: &str, format: ConfigFormat) -> Option<String> {
+    if format != ConfigFormat::Yaml {
+        return None;
+    }
+    let documents = serde_yaml::Deserializer::from_str(content)
+        .filter_map(|document| serde_yaml::Value::deserialize(document).ok())
+        .filter(|value| !value.is_null())
+        .count();
+    (documents > 1).then(|| {
+        format!(
+            "{} YAML documents (---) were deep-merged in order, later documents override earlier ones; \
+             profile-specific sections (e.g. spring.config.activate.on-profile) are mixed together",
+            documents
+        )
+    })
+}
+
+fn merge_value(target: &mut Value, source: Value) {
+    match (target, source) {
+        (Value::Object(target), Value::Object(source)) => {
+            for (key, value) in source {
+                merge_value(target.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (target, Value::Null) if !target.is_null() => {}
+        (target, source) => *target = source,
+    }
+}
+
+/// serde_yaml::Value 转 JSON，非字符串 key 转为字符串
+fn yaml_to_json(value: serde_yaml::Value) -> Value {
+    match value {
+        serde_yaml::Value::Null => Value::Null,
+        serde_yaml::Value::Bool(b) => Value::Bool(b),
+        serde_yaml::Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Value::from(i)
+            } else if let Some(u) = n.as_u64() {
+                Value::from(u)
+            } else {
+                n.as_f64().map(Value::from).unwrap_or(Value::Null)
+            }
+        }
+        serde_yaml::Value::String(s) => Value::String(s),
+        serde_yaml::Value::Sequence(seq) => Value::Array(seq.into_iter().map(yaml_to_json).collect()),
+        serde_yaml::Value::Mapping(mapping) => {
+            let mut map = Map::new();
+            for (k, v) in mapping {
+                map.insert(yaml_key_to_string(k), yaml_to_json(v));
+            }
+            Value::Object(map)
+        }
+        serde_yaml::Value::Tagged(tagged) => yaml_to_json(tagged.value),
+    }
+}
+
+fn yaml_key_to_string(key: serde_yaml::Value) -> String {
+    match key {
+        serde_yaml::Value::String(s) => s,
+        serde_yaml::Value::Bool(b) => b.to_string(),
+        serde_yaml::Value::Number(n) => n.to_string(),
+        serde_yaml::Value::Null => "null".to_string(),
+        other => serde_yaml::to_string(&other).unwrap_or_default().trim().to_string(),
+    }
+}
+
+/// 解析 Java properties，支持 = / : 分隔、# / ! 注释与行尾 \ 续行
+fn parse_properties(content: &str) -> Value {
+    let mut entries = Vec::new();
+    let mut pending = String::new();
+    for raw_line in content.lines() {
+        let line = raw_line.trim_start();
+        if pending.is_empty() && (line.is_empty() || line.starts_with('#') || line.starts_with('!')) {
+            continue;
+        }
+        if let Some(stripped) = line.strip_suffix('\\') {
+            pending.push_str(stripped);
+            continue;
+        }
+        pending.push_str(line);
+        entries.push(std::mem::take(&mut pending));
+    }
+    if !pending.is_empty() {
+        entries.push(pending);
+    }
+
+    let mut root = Map::new();
+    for entry in entries {
+        let split_at = entry.find(['=', ':']);
+        let (key, value) = match split_at {
+            Some(i) => (entry[..i].trim(), entry[i + 1..].trim()),
+            None => (entry.trim(), ""),
+        };
+        if key.is_empty() {
+            continue;
+        }
+        insert_dotted(&mut root, key, Value::String(value.to_string()));
+    }
+    Value::Object(root)
+}
+
+/// 按点分 key 插入嵌套结构；与已有叶子冲突时（如 a=1 与 a.b=2）保留完整 key，
+/// 与已有对象冲突时（如 a.b=1 与 a=2）把该对象退回为扁平 key（a.b），不覆盖已有值
+fn insert_dotted(root: &mut Map<String, Value>, key: &str, value: Value) {
+    if !can_nest(root, key) {
+        if let Some(Value::Object(_)) = root.get(key) {
+            if let Some(subtree) = root.remove(key) {
+                root.extend(flatten_config_with_prefix(key, &subtree));
+            }
+        }
+        root.insert(key.to_string(), value);
+        return;
+    }
+    let mut current = root;
+    let mut parts = key.split('.').peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            current.insert(part.to_string(), value);
+            return;
+        }
+        let entry = current
+            .entry(part.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+        let Value::Object(map) = entry else {
+            return;
+        };
+        current = map;
+    }
+}
+
+fn can_nest(root: &Map<String, Value>, key: &str) -> bool {
+    let parts: Vec<&str> = key.split('.').collect();
+    let mut current = root;
+    for (i, part) in parts.iter().enumerate() {
+        let is_last = i + 1 == parts.len();
+        match current.get(*part) {
+            None => return true,
+            Some(Value::Object(map)) if !is_last => current = map,
+            Some(Value::Object(_)) => return false,
+            Some(_) => return is_last,
+        }
+    }
+    true
+}
+
+/// 解析 XML：元素转为对象，属性记为 @name，同名子元素转为数组，纯文本元素直接取文本
+fn parse_xml(content: &str) -> Result<Value> {
+    let mut reader = Reader::from_str(content);
+    reader.trim_text(true);
+    // 栈中每一层为 (元素名, 子节点与属性, 文本)
+    let mut stack: Vec<(String, Map<String, Value>, String)> = vec![(String::new(), Map::new(), String::new())];
+    loop {
+        match reader.read_event()? {
+            Event::Start(start) => stack.push(xml_element(&start)?),
+            Event::Empty(start) => {
+                let (name, attrs, text) = xml_element(&start)?;
+                let parent = stack.last_mut().expect("xml stack is never empty");
+                insert_xml_child(&mut parent.1, name, xml_node_value(attrs, text));
+            }
+            Event::Text(text) => {
+                if let Some(top) = stack.last_mut() {
+                    top.2.push_str(&text.unescape()?);
+                }
+            }
+            Event::CData(data) => {
+                if let Some(top) = stack.last_mut() {
+                    top.2.push_str(&String::from_utf8_lossy(&data.into_inner()));
+                }
+            }
+            Event::End(_) => {
+                if stack.len() < 2 {
+                    return Err(anyhow::anyhow!("unbalanced XML end tag"));
+                }
+                let (name, map, text) = stack.pop().expect("checked stack length");
+                let parent = stack.last_mut().expect("xml stack is never empty");
+                insert_xml_child(&mut parent.1, name, xml_node_value(map, text));
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    if stack.len() != 1 {
+        return Err(anyhow::anyhow!("unexpected end of XML document"));
+    }
+    let (_, root, _) = stack.pop().expect("checked stack length");
+    Ok(Value::Object(root))
+}
+
+fn xml_element(start: &BytesStart) -> Result<(String, Map<String, Value>, String)> {
+    let name = String::from_utf8_lossy(start.name().as_ref()).to_string();
+    let mut attrs = Map::new();
+    for attr in start.attributes() {
+        let attr = attr?;
+        let key = format!("@{}", String::from_utf8_lossy(attr.key.as_ref()));
+        attrs.insert(key, Value::String(attr.unescape_value()?.to_string()));
+    }
+    Ok((name, attrs, String::new()))
+}
+
+fn xml_node_value(mut map: Map<String, Value>, text: String) -> Value {
+    let text = text.trim().to_string();
+    if map.is_empty() {
+        return Value::String(text);
+    }
+    if !text.is_empty() {
+        map.insert("#text".to_string(), Value::String(text));
+    }
+    Value::Object(map)
+}
+
+fn insert_xml_child(parent: &mut Map<String, Value>, name: String, value: Value) {
+    match parent.get_mut(&name) {
+        Some(Value::Array(items)) => items.push(value),
+        Some(existing) => {
+            let first = existing.take();
+            *existing = Value::Array(vec![first, value]);
+        }
+        None => {
+            parent.insert(name, value);
+        }
+    }
+}
+
+/// 路径中的一段
+#[derive(Debug, Clone, PartialEq)]
+enum PathSegment {
+    Key(String),
+    Index(usize),
+    Wildcard,
+}
+
+/// 解析类 JSONPath 的路径：spring.datasource.url、$.servers[0].host、list[*].name、['a.b'].c
+fn parse_path(path: &str) -> Result<Vec<PathSegment>> {
+    let path = path.trim();
+    let path = path.strip_prefix('$').unwrap_or(path);
+    let chars: Vec<char> = path.chars().collect();
+    let mut segments = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '.' => i += 1,
+            '[' => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|c| *c == ']')
+                    .map(|p| i + p)
+                    .ok_or_else(|| anyhow::anyhow!("unclosed '[' in path '{}'", path))?;
+                let inner: String = chars[i + 1..end].iter().collect();
+                let inner = inner.trim();
+                let segment = if inner == "*" {
+                    PathSegment::Wildcard
+                } else if let Ok(index) = inner.parse::<usize>() {
+                    PathSegment::Index(index)
+                } else {
+                    let quoted = inner
+                        .strip_prefix('\'')
+                        .and_then(|s| s.strip_suffix('\''))
+                        .or_else(|| inner.strip_prefix('"').and_then(|s| s.strip_suffix('"')));
+                    PathSegment::Key(quoted.unwrap_or(inner).to_string())
+                };
+                segments.push(segment);
+                i = end + 1;
+            }
+            _ => {
+                let start = i;
+                while i < chars.len() && chars[i] != '.' && chars[i] != '[' {
+                    i += 1;
+                }
+                let key: String = chars[start..i].iter().collect();
+                segments.push(if key == "*" {
+                    PathSegment::Wildcard
+                } else {
+                    PathSegment::Key(key)
+                });
+            }
+        }
+    }
+    Ok(segments)
+}
+
+fn format_path(prefix: &str, segment: &PathSegment, key: Option<&str>) -> String {
+    match (segment, key) {
+        (PathSegment::Index(i), _) => format!("{}[{}]", prefix, i),
+        (_, Some(key)) if prefix.is_empty() => key.to_string(),
+        (_, Some(key)) => format!("{}.{}", prefix, key),
+        (PathSegment::Key(key), None) if prefix.is_empty() => key.clone(),
+        (PathSegment::Key(key), None) => format!("{}.{}", prefix, key),
+        (PathSegment::Wildcard, None) => prefix.to_string(),
+    }
+}
+
+/// 按路径选择子树，返回 (实际路径, 值) 列表；通配符可能命中多个节点
+pub fn select_path(value: &Value, path: &str) -> Result<Vec<(String, Value)>> {
+    let segments = parse_path(path)?;
+    let mut current = vec![(String::new(), value)];
+    for segment in &segments {
+        let mut next = Vec::new();
+        for (prefix, node) in current {
+            match (segment, node) {
+                (PathSegment::Key(key), Value::Object(map)) => {
+                    if let Some(child) = map.get(key) {
+                        next.push((format_path(&prefix, segment, None), child));
+                    }
+                }
+                (PathSegment::Index(index), Value::Array(items)) => {
+                    if let Some(child) = items.get(*index) {
+                        next.push((format_path(&prefix, segment, None), child));
+                    }
+                }
+                (PathSegment::Wildcard, Value::Object(map)) => {
+                    for (key, child) in map {
+                        next.push((format_path(&prefix, segment, Some(key)), child));
+                    }
+                }
+                (PathSegment::Wildcard, Value::Array(items)) => {
+                    for (index, child) in items.iter().enumerate() {
+                        next.push((format_path(&prefix, &PathSegment::Index(index), None), child));
+                    }
+                }
+                _ => {}
+            }
+        }
+        current = next;
+    }
+    let mut selected: Vec<(String, Value)> = current
+        .into_iter()
+        .map(|(path, value)| (path, value.clone()))
+        .collect();
+
+    // 兜底：Spring 风格的 key 本身带点（如 "spring.profiles.active": dev），按展开后的 key 前缀匹配
+    if selected.is_empty() && !path.contains('*') {
+        let wanted = path.trim().trim_start_matches('$').trim_start_matches('.');
+        let mut subtree = Map::new();
+        for (key, leaf) in flatten_config(value) {
+            if key == wanted {
+                selected.push((key, leaf));
+            } else if let Some(rest) = key.strip_prefix(wanted).and_then(|r| r.strip_prefix('.')) {
+                subtree.insert(rest.to_string(), leaf);
+            }
+        }
+        if selected.is_empty() && !subtree.is_empty() {
+            selected.push((wanted.to_string(), Value::Object(subtree)));
+        }
+    }
+    Ok(selected)
+}
+
+/// 展开为 点分 key -> 值，数组元素使用 key[0] 形式
+pub fn flatten_config(value: &Value) -> BTreeMap<String, Value> {
+    flatten_config_with_prefix("", value)
+}
+
+pub fn flatten_config_with_prefix(prefix: &str, value: &Value) -> BTreeMap<String, Value> {
+    let mut out = BTreeMap::new();
+    flatten_into(prefix, value, &mut out);
+    out
+}
+
+fn flatten_into(prefix: &str, value: &Value, out: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (k, v) in map {
+                let key = if prefix.is_empty() {
+                    k.clone()
+                } else {
+                    format!("{}.{}", prefix, k)
+                };
+                flatten_into(&key, v, out);
+            }
+        }
+        Value::Array(items) if !items.is_empty() => {
+            for (i, v) in items.iter().enumerate() {
+                flatten_into(&format!("{}[{}]", prefix, i), v, out);
+            }
+        }
+        _ => {
+            out.insert(prefix.to_string(), value.clone());
+        }
+    }
+}
+
+const SECRET_KEY_MARKERS: &[&str] = &[
+    "password", "passwd", "pwd", "secret", "token", "credential",
+    "accesskey", "access-key", "access_key", "privatekey", "private-key", "private_key",
+    "apikey", "api-key", "api_key", "signingkey", "signing-key", "signing_key",
+];
+
+pub const MASKED_VALUE: &str = "******";
+
+/// 根据 key 的最后一段判断是否为敏感配置：包含敏感词，或形如 key、xxx-key、xxx_key、xxxKey
+pub fn is_secret_key(key: &str) -> bool {
+    let segment = key.rsplit('.').next().unwrap_or(key);
+    let segment = segment.split('[').next().unwrap_or(segment);
+    let last = segment.to_lowercase();
+    SECRET_KEY_MARKERS.iter().any(|marker| last.contains(marker))
+        || last == "key"
+        || last.ends_with("-key")
+        || last.ends_with("_key")
+        || segment.ends_with("Key")
+}
+
+pub fn mask_if_secret(key: &str, value: &Value) -> Value {
+    if is_secret_key(key) && !value.is_null() {
+        Value::String(MASKED_VALUE.to_string())
+    } else {
+        value.clone()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangedEntry {
+    pub key: String,
+    pub from: Value,
+    pub to: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyEntry {
+    pub key: String,
+    pub value: Value,
+}
+
+/// key 级别的差异，敏感配置的值已脱敏
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConfigDiff {
+    pub changed: Vec<ChangedEntry>,
+    pub only_in_from: Vec<KeyEntry>,
+    pub only_in_to: Vec<KeyEntry>,
+    pub unchanged_count: usize,
+}
+
+pub fn diff_configs(from: &Value, to: &Value) -> ConfigDiff {
+    let from = flatten_config(from);
+    let to = flatten_config(to);
+    let mut diff = ConfigDiff::default();
+    for (key, from_value) in &from {
+        match to.get(key) {
+            Some(to_value) if to_value == from_value => diff.unchanged_count += 1,
+            Some(to_value) => diff.changed.push(ChangedEntry {
+                key: key.clone(),
+                from: mask_if_secret(key, from_value),
+                to: mask_if_secret(key, to_value),
+            }),
+            None => diff.only_in_from.push(KeyEntry {
+                key: key.clone(),
+                value: mask_if_secret(key, from_value),
+            }),
+        }
+    }
+    for (key, to_value) in &to {
+        if !from.contains_key(key) {
+            diff.only_in_to.push(KeyEntry {
+                key: key.clone(),
+                value: mask_if_secret(key, to_value),
+            });
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn properties_nest_dotted_keys() {
+        let value = parse_properties("a.b=1\na.c: 2\nd=3");
+        assert_eq!(value, json!({ "a": { "b": "1", "c": "2" }, "d": "3" }));
+    }
+
+    #[test]
+    fn properties_keep_full_key_when_leaf_exists() {
+        let value = parse_properties("a=1\na.b=2");
+        assert_eq!(value, json!({ "a": "1", "a.b": "2" }));
+    }
+
+    #[test]
+    fn properties_flatten_object_when_key_conflicts() {
+        let value = parse_properties("a.b=1\na.c.d=2\na=3\na.e=4");
+        assert_eq!(value, json!({ "a": "3", "a.b": "1", "a.c.d": "2", "a.e": "4" }));
+        let flat = flatten_config(&value);
+        assert_eq!(flat.len(), 4);
+    }
+
+    #[test]
+    fn properties_nested_conflict_keeps_both() {
+        let value = parse_properties("a.b.c=1\na.b=2");
+        let flat = flatten_config(&value);
+        assert_eq!(flat.get("a.b.c"), Some(&json!("1")));
+        assert_eq!(flat.get("a.b"), Some(&json!("2")));
+    }
+
+    #[test]
+    fn masks_api_and_signing_keys() {
+        for key in [
+            "third-party.api-key",
+            "third-party.apiKey",
+            "gateway.api_key",
+            "jwt.signing-key",
+            "oss.sign_key",
+            "aes.key",
+            "client.appKey",
+            "sms.access-keys[0]",
+            "spring.datasource.password",
+        ] {
+            assert!(is_secret_key(key), "{}", key);
+        }
+        for key in ["cache.key-prefix", "redis.keyspace", "monkey.name", "server.port"] {
+            assert!(!is_secret_key(key), "{}", key);
+        }
+    }
+
+    #[test]
+    fn diff_masks_secret_values() {
+        let from = parse_properties("third-party.api-key=prod-key\nthird-party.url=https://a\n");
+        let to = parse_properties("third-party.api-key=test-key\nthird-party.url=https://b\n");
+        let diff = diff_configs(&from, &to);
+        let api_key = diff.changed.iter().find(|c| c.key == "third-party.api-key").unwrap();
+        assert_eq!(api_key.from, json!(MASKED_VALUE));
+        assert_eq!(api_key.to, json!(MASKED_VALUE));
+        let url = diff.changed.iter().find(|c| c.key == "third-party.url").unwrap();
+        assert_eq!(url.to, json!("https://b"));
+    }
+
+    fn sample() -> Value {
+        parse_config(
+            "spring:\n  datasource:\n    url: jdbc:mysql://db/app\n    username: app\n\
+             servers:\n  - host: a\n    port: 80\n  - host: b\n    port: 81\n\
+             \"log.level\": debug\n",
+            ConfigFormat::Yaml,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn select_dotted_path() {
+        let selected = select_path(&sample(), "spring.datasource.url").unwrap();
+        assert_eq!(selected, vec![("spring.datasource.url".to_string(), json!("jdbc:mysql://db/app"))]);
+        let selected = select_path(&sample(), "$.spring.datasource").unwrap();
+        assert_eq!(selected[0].1["username"], json!("app"));
+        assert!(select_path(&sample(), "spring.redis").unwrap().is_empty());
+    }
+
+    #[test]
+    fn select_index_and_wildcard() {
+        let selected = select_path(&sample(), "servers[1].host").unwrap();
+        assert_eq!(selected, vec![("servers[1].host".to_string(), json!("b"))]);
+        let selected = select_path(&sample(), "servers[*].port").unwrap();
+        assert_eq!(
+            selected,
+            vec![
+                ("servers[0].port".to_string(), json!(80)),
+                ("servers[1].port".to_string(), json!(81)),
+            ]
+        );
+        let selected = select_path(&sample(), "spring.datasource.*").unwrap();
+        let paths: Vec<&str> = selected.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(paths, ["spring.datasource.url", "spring.datasource.username"]);
+        assert!(select_path(&sample(), "servers[5]").unwrap().is_empty());
+        assert!(select_path(&sample(), "servers[0").is_err());
+    }
+
+    #[test]
+    fn select_quoted_key() {
+        let selected = select_path(&sample(), "['log.level']").unwrap();
+        assert_eq!(selected, vec![("log.level".to_string(), json!("debug"))]);
+        let selected = select_path(&sample(), "[\"log.level\"]").unwrap();
+        assert_eq!(selected[0].1, json!("debug"));
+    }
+
+    #[test]
+    fn select_falls_back_to_spring_dotted_keys() {
+        let value = parse_config(
+            "spring.datasource.url: jdbc:mysql://db/app\nspring.datasource.username: app\n",
+            ConfigFormat::Yaml,
+        )
+        .unwrap();
+        let selected = select_path(&value, "spring.datasource.url").unwrap();
+        assert_eq!(selected, vec![("spring.datasource.url".to_string(), json!("jdbc:mysql://db/app"))]);
+        let selected = select_path(&value, "spring.datasource").unwrap();
+        assert_eq!(
+            selected,
+            vec![(
+                "spring.datasource".to_string(),
+                json!({ "url": "jdbc:mysql://db/app", "username": "app" })
+            )]
+        );
+    }
+
+    #[test]
+    fn xml_attributes_and_repeated_elements() {
+        let value = parse_config(
+            "<config env=\"test\"><server id=\"1\">a</server><server id=\"2\"/><timeout>30</timeout></config>",
+            ConfigFormat::Xml,
+        )
+        .unwrap();
+        assert_eq!(
+            value,
+            json!({ "config": {
+                "@env": "test",
+                "server": [{ "@id": "1", "#text": "a" }, { "@id": "2" }],
+                "timeout": "30",
+            } })
+        );
+        let selected = select_path(&value, "config.server[*].@id").unwrap();
+        let ids: Vec<Value> = selected.into_iter().map(|(_, id)| id).collect();
+        assert_eq!(ids, [json!("1"), json!("2")]);
+        assert!(parse_config("<a><b></a>", ConfigFormat::Xml).is_err());
+    }
+
+    #[test]
+    fn detects_format_from_data_id_and_content() {
+        assert_eq!(detect_format("app.yml", "a=1"), ConfigFormat::Yaml);
+        assert_eq!(detect_format("app.properties", "a: 1"), ConfigFormat::Properties);
+        assert_eq!(detect_format("app", "# comment\nserver.port=8080\n"), ConfigFormat::Properties);
+        assert_eq!(detect_format("app", "server:\n  port: 8080\n"), ConfigFormat::Yaml);
+        assert_eq!(detect_format("app", "url: jdbc:mysql://db?a=1\n"), ConfigFormat::Yaml);
+        assert_eq!(detect_format("app", "{\"a\":1}"), ConfigFormat::Json);
+        assert_eq!(detect_format("app", "<a/>"), ConfigFormat::Xml);
+    }
+
+    #[test]
+    fn multi_document_yaml_is_merged_and_reported() {
+        let content = "server:\n  port: 8080\n---\nspring.config.activate.on-profile: prod\nserver:\n  port: 9090\n";
+        let value = parse_config(content, ConfigFormat::Yaml).unwrap();
+        assert_eq!(value["server"]["port"], json!(9090));
+        let note = merged_documents_note(content, ConfigFormat::Yaml).unwrap();
+        assert!(note.starts_with("2 YAML documents"));
+        assert_eq!(merged_documents_note("---\nserver:\n  port: 1\n", ConfigFormat::Yaml), None);
+        assert_eq!(merged_documents_note("a=1", ConfigFormat::Properties), None);
+    }
+}
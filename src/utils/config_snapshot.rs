@@ -0,0 +1,138 @@
+// 配置本地快照与运行状态：Nacos 不可用时回退到最近一次成功加载的配置
+use crate::utils::date_util::now_datetime_string;
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+pub const MAIN_CONFIG_SNAPSHOT: &str = "main-config.yml";
+pub const ES_FIELDS_SNAPSHOT: &str = "elasticsearch-fields.yml";
+
+pub fn config_snapshot_dir() -> String {
+    env::var("CONFIG_SNAPSHOT_DIR").unwrap_or_else(|_| ".mcp-config-cache".to_string())
+}
+
+/// 配置来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigSource {
+    Uninitialized, // 尚未加载
+    Live,          // 来自 Nacos 的实时配置
+    Cached,        // Nacos 不可用，使用本地快照
+    File,          // 离线模式，使用本地配置文件
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigStatus {
+    pub source: ConfigSource,
+    pub since: String,                    // 进入当前状态的时间
+    pub last_live_update: Option<String>, // 最近一次从 Nacos 成功加载的时间
+    pub last_error: Option<String>,       // 最近一次连接 Nacos 的错误
+    pub snapshot_dir: String,
+}
+
+static CONFIG_STATUS: Lazy<RwLock<ConfigStatus>> = Lazy::new(|| {
+    RwLock::new(ConfigStatus {
+        source: ConfigSource::Uninitialized,
+        since: now_datetime_string(),
+        last_live_update: None,
+        last_error: None,
+        snapshot_dir: config_snapshot_dir(),
+    })
+});
+
+pub fn get_config_status() -> ConfigStatus {
+    CONFIG_STATUS.read().unwrap().clone()
+}
+
+/// 切换配置来源，来源未变化时保留原来的 since
+pub fn set_config_source(source: ConfigSource) {
+    let mut guard = CONFIG_STATUS.write().unwrap();
+    let now = now_datetime_string();
+    if guard.source != source {
+        guard.source = source;
+        guard.since = now.clone();
+    }
+    if source == ConfigSource::Live {
+        guard.last_live_update = Some(now);
+        guard.last_error = None;
+    }
+}
+
+pub fn set_config_error(error: &str) {
+    let mut guard = CONFIG_STATUS.write().unwrap();
+    guard.last_error = Some(error.to_string());
+}
+
+fn snapshot_path(name: &str) -> PathBuf {
+    PathBuf::from(config_snapshot_dir()).join(name)
+}
+
+/// 快照包含数据库、ES、Nacos 等密码，目录与文件仅对当前用户可读写（unix 下 0700 / 0600）
+fn create_private_dir(dir: &Path) -> Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(dir)?;
+    Ok(())
+}
+
+fn write_private_file(path: &Path, content: &str) -> Result<()> {
+    // 先删除旧文件，mode 只在创建时生效
+    let _ = fs::remove_file(path);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(content.as_bytes())?;
+    Ok(())
+}
+
+/// 写入快照，先写临时文件再重命名，避免进程中断留下半截内容
+pub fn save_snapshot(name: &str, content: &str) -> Result<()> {
+    let dir = PathBuf::from(config_snapshot_dir());
+    create_private_dir(&dir)?;
+    let path = snapshot_path(name);
+    let tmp_path = dir.join(format!("{}.tmp", name));
+    write_private_file(&tmp_path, content)?;
+    fs::rename(&tmp_path, &path)?;
+    Ok(())
+}
+
+pub fn load_snapshot(name: &str) -> Result<String> {
+    let path = snapshot_path(name);
+    fs::read_to_string(&path)
+        .map_err(|e| anyhow::anyhow!("读取配置快照 {} 失败: {}", path.display(), e))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn snapshot_files_are_private() {
+        let dir = env::temp_dir().join(format!("mcp-snapshot-test-{}", std::process::id()));
+        create_private_dir(&dir).unwrap();
+        let path = dir.join(MAIN_CONFIG_SNAPSHOT);
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_private_file(&path, "password: secret").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "password: secret");
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        assert_eq!(fs::metadata(&dir).unwrap().permissions().mode() & 0o777, 0o700);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,6 +1,289 @@
-use chrono::Local;
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+use std::env;
 
 /// 获取当前日期时间，格式为 "YYYY-MM-DD HH:MM:SS"
 pub fn now_datetime_string() -> String {
     Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
 }
+
+/// 默认时区（IANA 名称），用于解析不带时区的时间与输出本地时间
+pub fn default_time_zone() -> String {
+    env::var("TIME_ZONE").unwrap_or_else(|_| "Asia/Shanghai".to_string())
+}
+
+pub fn parse_time_zone(name: &str) -> Result<Tz> {
+    name.trim()
+        .parse::<Tz>()
+        .map_err(|_| anyhow::anyhow!("Unknown time zone '{}', expected an IANA name such as Asia/Shanghai or UTC", name))
+}
+
+/// 未指定时区时使用 TIME_ZONE
+pub fn resolve_time_zone(name: Option<&str>) -> Result<Tz> {
+    match name.map(str::trim).filter(|n| !n.is_empty()) {
+        Some(name) => parse_time_zone(name),
+        None => parse_time_zone(&default_time_zone()),
+    }
+}
+
+/// 同一时刻的多种表示：SLS 使用秒级时间戳，ES 使用带偏移的 RFC3339
+#[derive(Debug, Clone, Serialize)]
+pub struct TimeFormats {
+    pub epoch_seconds: i64,
+    pub epoch_millis: i64,
+    pub rfc3339_utc: String,
+    pub rfc3339: String,
+    pub datetime: String, // 指定时区下的 "YYYY-MM-DD HH:MM:SS"
+    pub time_zone: String,
+}
+
+impl TimeFormats {
+    pub fn new(time: DateTime<Utc>, tz: Tz) -> Self {
+        let zoned = time.with_timezone(&tz);
+        Self {
+            epoch_seconds: time.timestamp(),
+            epoch_millis: time.timestamp_millis(),
+            rfc3339_utc: time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            rfc3339: zoned.to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+            datetime: zoned.format("%Y-%m-%d %H:%M:%S").to_string(),
+            time_zone: tz.name().to_string(),
+        }
+    }
+}
+
+fn zoned_to_utc(naive: NaiveDateTime, tz: Tz) -> Result<DateTime<Utc>> {
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok_or_else(|| anyhow::anyhow!("Time {} does not exist in time zone {}", naive, tz.name()))
+}
+
+/// 解析单个时间点：秒/毫秒时间戳、RFC3339，或按 tz 解释的 "YYYY-MM-DD[ HH:MM[:SS]]"
+pub fn parse_time(input: &str, tz: Tz) -> Result<DateTime<Utc>> {
+    // 统一大写，兼容 2024-06-01t10:00:00z 这类小写写法
+    let input = input.trim().to_uppercase();
+    let input = input.as_str();
+    if let Ok(number) = input.parse::<i64>() {
+        // 超过 11 位按毫秒处理
+        let time = if input.trim_start_matches('-').len() > 11 {
+            DateTime::from_timestamp_millis(number)
+        } else {
+            DateTime::from_timestamp(number, 0)
+        };
+        return time.ok_or_else(|| anyhow::anyhow!("Timestamp {} is out of range", number));
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(input) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(input, format) {
+            return zoned_to_utc(naive, tz);
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        return zoned_to_utc(date.and_time(NaiveTime::MIN), tz);
+    }
+    Err(anyhow::anyhow!(
+        "Unrecognized time '{}', expected epoch seconds/millis, RFC3339 or YYYY-MM-DD HH:MM:SS",
+        input
+    ))
+}
+
+/// 单位换算为秒，仅允许已知单位词去掉一个复数 s，"ms" 等不支持的单位返回 None
+fn parse_unit(unit: &str) -> Option<i64> {
+    let unit = match unit.strip_suffix('s') {
+        Some(word @ ("sec" | "second" | "min" | "minute" | "hr" | "hour" | "day" | "week")) => word,
+        _ => unit,
+    };
+    let seconds = match unit {
+        "s" | "sec" | "second" => 1,
+        "m" | "min" | "minute" => 60,
+        "h" | "hr" | "hour" => 3600,
+        "d" | "day" => 86400,
+        "w" | "week" => 7 * 86400,
+        _ => return None,
+    };
+    Some(seconds)
+}
+
+/// 解析时长："15 minutes"、"2h"、"1 day"；不是时长时返回 Ok(None)，超出可表示范围时返回错误
+fn parse_duration(text: &str) -> Result<Option<Duration>> {
+    let text = text.trim();
+    let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let (amount, unit) = text.split_at(split);
+    let Some(seconds) = parse_unit(unit.trim()) else {
+        return Ok(None);
+    };
+    let out_of_range = || anyhow::anyhow!("Duration '{}' is out of range", text);
+    let amount: i64 = if amount.is_empty() { 1 } else { amount.parse().map_err(|_| out_of_range())? };
+    amount
+        .checked_mul(seconds)
+        .and_then(Duration::try_seconds)
+        .map(Some)
+        .ok_or_else(out_of_range)
+}
+
+/// now 往前推 duration，超出可表示的时间范围时返回错误
+fn subtract_duration(now: DateTime<Utc>, duration: Duration) -> Result<DateTime<Utc>> {
+    now.checked_sub_signed(duration)
+        .ok_or_else(|| anyhow::anyhow!("Time {} before now is out of range", duration))
+}
+
+/// 相对时间段："last 15 minutes"、"past 2h" 或以数字开头的 "15m"
+fn relative_duration(text: &str) -> Result<Option<Duration>> {
+    match ["last ", "past "].iter().find_map(|prefix| text.strip_prefix(prefix)) {
+        Some(rest) => parse_duration(rest)?
+            .map(Some)
+            .ok_or_else(|| anyhow::anyhow!("Invalid duration '{}'", rest)),
+        None if text.starts_with(|c: char| c.is_ascii_digit()) => parse_duration(text),
+        None => Ok(None),
+    }
+}
+
+/// 日期关键字解析结果：起始时刻，以及仅有日期时的整天结束时刻
+type DayKeyword = (DateTime<Utc>, Option<DateTime<Utc>>);
+
+/// 解析带日期关键字的时间点：today / yesterday [HH:MM[:SS]]
+fn parse_day_keyword(text: &str, now: DateTime<Utc>, tz: Tz) -> Result<Option<DayKeyword>> {
+    let mut parts = text.split_whitespace();
+    let day = match parts.next() {
+        Some("today") => now.with_timezone(&tz).date_naive(),
+        Some("yesterday") => now.with_timezone(&tz).date_naive() - Duration::days(1),
+        _ => return Ok(None),
+    };
+    match parts.next() {
+        Some(time) => {
+            let time = NaiveTime::parse_from_str(time, "%H:%M:%S")
+                .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M"))
+                .map_err(|_| anyhow::anyhow!("Invalid time of day '{}', expected HH:MM or HH:MM:SS", time))?;
+            Ok(Some((zoned_to_utc(day.and_time(time), tz)?, None)))
+        }
+        // 仅有日期关键字时表示整天，今天截止到当前
+        None => {
+            let start = zoned_to_utc(day.and_time(NaiveTime::MIN), tz)?;
+            let end = zoned_to_utc((day + Duration::days(1)).and_time(NaiveTime::MIN), tz)?.min(now);
+            Ok(Some((start, Some(end))))
+        }
+    }
+}
+
+fn parse_point(text: &str, now: DateTime<Utc>, tz: Tz) -> Result<DateTime<Utc>> {
+    if text == "now" {
+        return Ok(now);
+    }
+    if let Some(ago) = text.strip_suffix(" ago") {
+        let duration = parse_duration(ago)?.ok_or_else(|| anyhow::anyhow!("Invalid duration '{}'", ago))?;
+        return subtract_duration(now, duration);
+    }
+    if let Some((start, _)) = parse_day_keyword(text, now, tz)? {
+        return Ok(start);
+    }
+    parse_time(text, tz)
+}
+
+/// 将时间表达式解析为 (from, to)，末尾可带 IANA 时区覆盖默认时区。支持：
+/// - "last 15 minutes"、"past 2h"、"15m"：截止到当前的相对时间段
+/// - "today"、"yesterday"：整天
+/// - "yesterday 09:00"、"2 hours ago"、"2024-06-01 10:00"：从该时刻到当前
+/// - "A to B" 或 "A ~ B"：两端各自按上述规则解析
+pub fn resolve_time_range(expression: &str, tz: Tz, now: DateTime<Utc>) -> Result<(DateTime<Utc>, DateTime<Utc>, Tz)> {
+    let mut text = expression.trim().to_lowercase();
+    let mut tz = tz;
+    // 时区名大小写敏感，取原始表达式的最后一个词判断
+    if let Some(last) = expression.split_whitespace().last() {
+        if last.contains('/') || last.eq_ignore_ascii_case("utc") {
+            if let Ok(zone) = parse_time_zone(last) {
+                tz = zone;
+                text = text
+                    .rsplit_once(char::is_whitespace)
+                    .map(|(head, _)| head.trim().to_string())
+                    .unwrap_or_default();
+            }
+        }
+    }
+    if text.is_empty() {
+        return Err(anyhow::anyhow!("Empty time expression"));
+    }
+
+    let separator = [" to ", "~"].into_iter().find(|sep| text.contains(sep));
+    let (from, to) = if let Some((from, to)) = separator.and_then(|sep| text.split_once(sep)) {
+        (parse_point(from.trim(), now, tz)?, parse_point(to.trim(), now, tz)?)
+    } else if let Some(duration) = relative_duration(&text)? {
+        (subtract_duration(now, duration)?, now)
+    } else if let Some((start, end)) = parse_day_keyword(&text, now, tz)? {
+        (start, end.unwrap_or(now))
+    } else {
+        (parse_point(&text, now, tz)?, now)
+    };
+
+    if from >= to {
+        return Err(anyhow::anyhow!(
+            "Time expression '{}' resolves to an empty range ({} >= {})",
+            expression,
+            from.to_rfc3339(),
+            to.to_rfc3339()
+        ));
+    }
+    Ok((from, to, tz))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_accepts_short_and_long_units() {
+        let cases = [
+            ("30s", 30),
+            ("1 sec", 1),
+            ("10 seconds", 10),
+            ("15m", 15 * 60),
+            ("15 mins", 15 * 60),
+            ("15 minutes", 15 * 60),
+            ("2h", 2 * 3600),
+            ("3 hrs", 3 * 3600),
+            ("1 hour", 3600),
+            ("hour", 3600),
+            ("1 day", 86400),
+            ("2 days", 2 * 86400),
+            ("1w", 7 * 86400),
+            ("2 weeks", 14 * 86400),
+        ];
+        for (text, seconds) in cases {
+            assert_eq!(parse_duration(text).unwrap(), Some(Duration::seconds(seconds)), "{}", text);
+        }
+    }
+
+    #[test]
+    fn parse_duration_rejects_unknown_units() {
+        for text in ["15ms", "15 ms", "5 mss", "3 hourss", "2 dayz", "10", "", "abc"] {
+            assert_eq!(parse_duration(text).unwrap(), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn resolve_time_range_rejects_milliseconds() {
+        assert!(resolve_time_range("last 15ms", Tz::UTC, Utc::now()).is_err());
+        let now = Utc::now();
+        let (from, to, _) = resolve_time_range("last 15m", Tz::UTC, now).unwrap();
+        assert_eq!(to - from, Duration::minutes(15));
+    }
+
+    #[test]
+    fn huge_durations_are_out_of_range_errors() {
+        let now = Utc::now();
+        for expression in [
+            "last 100000000 weeks",
+            "100000000 weeks ago",
+            "last 99999999999999999999 days",
+            "9223372036854775807 weeks",
+            "2024-06-01 to 100000000 weeks ago",
+        ] {
+            let error = resolve_time_range(expression, Tz::UTC, now).unwrap_err();
+            assert!(error.to_string().contains("out of range"), "{}: {}", expression, error);
+        }
+        assert!(parse_duration("9223372036854775807 weeks").is_err());
+    }
+}
@@ -0,0 +1,388 @@
+// Elasticsearch 聚合助手：按时间范围、时间间隔与分组字段组装 date_histogram / terms 聚合，并把结果展开为二维表
+use crate::utils::es_version::EsVersion;
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::env;
+
+const DEFAULT_PERCENTS: [f64; 3] = [50.0, 95.0, 99.0];
+const CALENDAR_INTERVALS: [&str; 11] = [
+    "minute", "hour", "day", "week", "month", "quarter", "year", "1w", "1M", "1q", "1y",
+];
+
+/// 每个分组字段返回的最大分组数
+pub fn es_agg_terms_size() -> u64 {
+    env::var("ES_AGG_TERMS_SIZE")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(10)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AggMetric {
+    Count,
+    Avg(String),
+    Sum(String),
+    Percentiles(String, Vec<f64>),
+}
+
+impl AggMetric {
+    /// 解析 "count"、"avg:field"、"sum:field"、"percentiles:field" 或 "percentiles:field:50,90,99"
+    pub fn parse(input: &str) -> Result<Self> {
+        let input = input.trim();
+        let mut parts = input.splitn(3, ':');
+        let kind = parts.next().unwrap_or("").trim().to_lowercase();
+        let field = parts.next().map(str::trim).filter(|f| !f.is_empty());
+        let extra = parts.next().map(str::trim);
+        let require_field = || {
+            field
+                .map(str::to_string)
+                .ok_or_else(|| anyhow::anyhow!("Metric '{}' requires a field, e.g. {}:duration", input, kind))
+        };
+        match kind.as_str() {
+            "" | "count" => Ok(Self::Count),
+            "avg" => Ok(Self::Avg(require_field()?)),
+            "sum" => Ok(Self::Sum(require_field()?)),
+            "percentiles" => {
+                let percents = match extra.filter(|e| !e.is_empty()) {
+                    Some(list) => list
+                        .split(',')
+                        .map(|p| {
+                            p.trim()
+                                .parse::<f64>()
+                                .ok()
+                                .filter(|p| (0.0..=100.0).contains(p))
+                                .ok_or_else(|| anyhow::anyhow!("Invalid percentile '{}'", p.trim()))
+                        })
+                        .collect::<Result<Vec<f64>>>()?,
+                    None => DEFAULT_PERCENTS.to_vec(),
+                };
+                Ok(Self::Percentiles(require_field()?, percents))
+            }
+            _ => Err(anyhow::anyhow!(
+                "Unsupported metric '{}', supported: count, avg:<field>, sum:<field>, percentiles:<field>[:50,95,99]",
+                input
+            )),
+        }
+    }
+
+    pub fn field(&self) -> Option<&str> {
+        match self {
+            Self::Count => None,
+            Self::Avg(field) | Self::Sum(field) | Self::Percentiles(field, _) => Some(field),
+        }
+    }
+
+    fn columns(&self) -> Vec<String> {
+        match self {
+            Self::Count => Vec::new(),
+            Self::Avg(field) => vec![format!("avg({})", field)],
+            Self::Sum(field) => vec![format!("sum({})", field)],
+            Self::Percentiles(field, percents) => {
+                percents.iter().map(|p| format!("p{}({})", p, field)).collect()
+            }
+        }
+    }
+
+    fn aggregation(&self) -> Option<Value> {
+        match self {
+            Self::Count => None,
+            Self::Avg(field) => Some(json!({ "avg": { "field": field } })),
+            Self::Sum(field) => Some(json!({ "sum": { "field": field } })),
+            Self::Percentiles(field, percents) => Some(json!({
+                "percentiles": { "field": field, "percents": percents, "keyed": false }
+            })),
+        }
+    }
+
+    /// 叶子桶中的指标值，顺序与 columns 一致
+    fn values(&self, bucket: &Value) -> Vec<Value> {
+        let metric = &bucket["metric"];
+        match self {
+            Self::Count => Vec::new(),
+            Self::Avg(_) | Self::Sum(_) => vec![metric["value"].clone()],
+            Self::Percentiles(_, percents) => {
+                let values = metric["values"].as_array().cloned().unwrap_or_default();
+                percents
+                    .iter()
+                    .map(|p| {
+                        values
+                            .iter()
+                            .find(|v| v["key"].as_f64() == Some(*p))
+                            .map(|v| v["value"].clone())
+                            .unwrap_or(Value::Null)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AggregationRequest {
+    pub time_field: String,
+    pub from_millis: i64,
+    pub to_millis: i64,
+    pub time_zone: String,
+    pub interval: Option<String>,
+    pub group_by: Vec<String>,
+    pub metric: AggMetric,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AggregationTable {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+    pub total_hits: Value,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// date_histogram 间隔：7.2 之前只有 interval，之后按单位区分 calendar_interval / fixed_interval
+fn interval_clause(interval: &str, version: &EsVersion) -> (&'static str, String) {
+    let interval = interval.trim();
+    if !version.supports_split_intervals() {
+        return ("interval", interval.to_string());
+    }
+    if CALENDAR_INTERVALS.contains(&interval) {
+        ("calendar_interval", interval.to_string())
+    } else {
+        ("fixed_interval", interval.to_string())
+    }
+}
+
+/// 组装搜索请求体：size=0，时间范围过滤，外层 date_histogram，逐层嵌套 terms，最内层为指标
+pub fn build_aggregation_query(request: &AggregationRequest, version: &EsVersion) -> Value {
+    let mut inner: Option<Value> = request.metric.aggregation().map(|metric| json!({ "metric": metric }));
+    for (level, field) in request.group_by.iter().enumerate().rev() {
+        let mut terms = json!({ "terms": { "field": field, "size": es_agg_terms_size() } });
+        if let Some(aggs) = inner.take() {
+            terms["aggs"] = aggs;
+        }
+        let mut wrapper = Map::new();
+        wrapper.insert(format!("group_{}", level), terms);
+        inner = Some(Value::Object(wrapper));
+    }
+    if let Some(interval) = &request.interval {
+        let (key, value) = interval_clause(interval, version);
+        let mut histogram = json!({
+            "date_histogram": {
+                "field": request.time_field,
+                key: value,
+                "time_zone": request.time_zone,
+                "min_doc_count": 0,
+                "extended_bounds": { "min": request.from_millis, "max": request.to_millis },
+            }
+        });
+        if let Some(aggs) = inner.take() {
+            histogram["aggs"] = aggs;
+        }
+        inner = Some(json!({ "time": histogram }));
+    }
+
+    let mut body = json!({
+        "size": 0,
+        "query": {
+            "bool": {
+                "filter": [{
+                    "range": {
+                        request.time_field.as_str(): {
+                            "gte": request.from_millis,
+                            "lte": request.to_millis,
+                            "format": "epoch_millis",
+                        }
+                    }
+                }]
+            }
+        },
+    });
+    if let Some(aggs) = inner {
+        body["aggs"] = aggs;
+    }
+    body
+}
+
+/// 把嵌套桶展开为行：[time] + 分组字段 + doc_count + 指标列
+pub fn flatten_aggregation_response(request: &AggregationRequest, body: &Value) -> AggregationTable {
+    let mut columns = Vec::new();
+    if request.interval.is_some() {
+        columns.push(request.time_field.clone());
+    }
+    columns.extend(request.group_by.iter().cloned());
+    columns.push("doc_count".to_string());
+    columns.extend(request.metric.columns());
+
+    let mut table = AggregationTable {
+        columns,
+        rows: Vec::new(),
+        total_hits: body.pointer("/hits/total").cloned().unwrap_or(Value::Null),
+        warnings: Vec::new(),
+    };
+    let aggregations = body.get("aggregations").cloned().unwrap_or(Value::Null);
+    let mut omitted = vec![0; request.group_by.len()];
+    if request.interval.is_some() {
+        for bucket in buckets(&aggregations["time"]) {
+            flatten_groups(request, bucket, 0, vec![bucket_key(bucket)], &mut omitted, &mut table);
+        }
+    } else {
+        flatten_groups(request, &aggregations, 0, Vec::new(), &mut omitted, &mut table);
+    }
+    for (field, count) in request.group_by.iter().zip(omitted).filter(|(_, count)| *count > 0) {
+        table.warnings.push(format!(
+            "{} documents fall into '{}' groups beyond the top {} and are not listed",
+            count,
+            field,
+            es_agg_terms_size()
+        ));
+    }
+    table
+}
+
+fn buckets(aggregation: &Value) -> &[Value] {
+    aggregation["buckets"].as_array().map(Vec::as_slice).unwrap_or(&[])
+}
+
+fn bucket_key(bucket: &Value) -> Value {
+    bucket.get("key_as_string").cloned().unwrap_or_else(|| bucket["key"].clone())
+}
+
+fn flatten_groups(
+    request: &AggregationRequest,
+    parent: &Value,
+    level: usize,
+    prefix: Vec<Value>,
+    omitted: &mut [u64],
+    table: &mut AggregationTable,
+) {
+    if level == request.group_by.len() {
+        // 没有 interval 和分组时，parent 为 aggregations 本身，文档数取 hits.total（6.x 为数字，7.x+ 为 {value}）
+        let doc_count = parent.get("doc_count").cloned().unwrap_or_else(|| {
+            table
+                .total_hits
+                .as_u64()
+                .or(table.total_hits["value"].as_u64())
+                .map(Value::from)
+                .unwrap_or(Value::Null)
+        });
+        let mut row = prefix;
+        row.push(doc_count);
+        row.extend(request.metric.values(parent));
+        table.rows.push(row);
+        return;
+    }
+    let aggregation = &parent[format!("group_{}", level)];
+    omitted[level] += aggregation["sum_other_doc_count"].as_u64().unwrap_or(0);
+    for bucket in buckets(aggregation) {
+        let mut row = prefix.clone();
+        row.push(bucket_key(bucket));
+        flatten_groups(request, bucket, level + 1, row, omitted, table);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(interval: Option<&str>, group_by: &[&str], metric: AggMetric) -> AggregationRequest {
+        AggregationRequest {
+            time_field: "@timestamp".to_string(),
+            from_millis: 0,
+            to_millis: 3_600_000,
+            time_zone: "UTC".to_string(),
+            interval: interval.map(str::to_string),
+            group_by: group_by.iter().map(|f| f.to_string()).collect(),
+            metric,
+        }
+    }
+
+    #[test]
+    fn count_without_buckets_uses_total_hits() {
+        let request = request(None, &[], AggMetric::Count);
+        let table = flatten_aggregation_response(&request, &json!({ "hits": { "total": { "value": 42, "relation": "eq" } } }));
+        assert_eq!(table.columns, ["doc_count"]);
+        assert_eq!(table.rows, vec![vec![json!(42)]]);
+
+        // 6.x 未归一化时 hits.total 为数字
+        let table = flatten_aggregation_response(&request, &json!({ "hits": { "total": 7 } }));
+        assert_eq!(table.rows, vec![vec![json!(7)]]);
+    }
+
+    #[test]
+    fn metric_without_buckets() {
+        let request = request(None, &[], AggMetric::Avg("duration".to_string()));
+        let body = json!({
+            "hits": { "total": { "value": 3, "relation": "eq" } },
+            "aggregations": { "metric": { "value": 12.5 } },
+        });
+        let table = flatten_aggregation_response(&request, &body);
+        assert_eq!(table.columns, ["doc_count", "avg(duration)"]);
+        assert_eq!(table.rows, vec![vec![json!(3), json!(12.5)]]);
+    }
+
+    #[test]
+    fn histogram_with_nested_groups() {
+        let request = request(
+            Some("30m"),
+            &["service", "level"],
+            AggMetric::Percentiles("duration".to_string(), vec![50.0, 99.0]),
+        );
+        let leaf = |key: &str, count: u64| {
+            json!({
+                "key": key,
+                "doc_count": count,
+                "metric": { "values": [{ "key": 50.0, "value": 10.0 }, { "key": 99.0, "value": 90.0 }] },
+            })
+        };
+        let body = json!({
+            "hits": { "total": { "value": 6, "relation": "eq" } },
+            "aggregations": { "time": { "buckets": [
+                {
+                    "key": 0,
+                    "key_as_string": "2024-06-01T00:00:00Z",
+                    "doc_count": 6,
+                    "group_0": {
+                        "sum_other_doc_count": 2,
+                        "buckets": [{
+                            "key": "order",
+                            "doc_count": 4,
+                            "group_1": { "sum_other_doc_count": 0, "buckets": [leaf("ERROR", 1), leaf("INFO", 3)] },
+                        }],
+                    },
+                },
+                {
+                    "key": 1_800_000,
+                    "key_as_string": "2024-06-01T00:30:00Z",
+                    "doc_count": 0,
+                    "group_0": { "sum_other_doc_count": 0, "buckets": [] },
+                },
+            ] } },
+        });
+        let table = flatten_aggregation_response(&request, &body);
+        assert_eq!(
+            table.columns,
+            ["@timestamp", "service", "level", "doc_count", "p50(duration)", "p99(duration)"]
+        );
+        assert_eq!(
+            table.rows,
+            vec![
+                vec![json!("2024-06-01T00:00:00Z"), json!("order"), json!("ERROR"), json!(1), json!(10.0), json!(90.0)],
+                vec![json!("2024-06-01T00:00:00Z"), json!("order"), json!("INFO"), json!(3), json!(10.0), json!(90.0)],
+            ]
+        );
+        assert_eq!(table.warnings.len(), 1);
+        assert!(table.warnings[0].starts_with("2 documents fall into 'service' groups"));
+    }
+
+    #[test]
+    fn metric_parse() {
+        assert_eq!(AggMetric::parse("").unwrap(), AggMetric::Count);
+        assert_eq!(AggMetric::parse("sum:bytes").unwrap(), AggMetric::Sum("bytes".to_string()));
+        assert_eq!(
+            AggMetric::parse("percentiles:duration").unwrap(),
+            AggMetric::Percentiles("duration".to_string(), DEFAULT_PERCENTS.to_vec())
+        );
+        assert!(AggMetric::parse("avg").is_err());
+        assert!(AggMetric::parse("percentiles:duration:101").is_err());
+        assert!(AggMetric::parse("max:duration").is_err());
+    }
+}
@@ -0,0 +1,99 @@
+// Elasticsearch HTTP 错误：解析 error.type / error.reason / root_cause，作为结构化工具错误返回
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EsErrorCause {
+    #[serde(rename = "type")]
+    pub error_type: Option<String>,
+    pub reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<String>,
+}
+
+impl EsErrorCause {
+    fn from_value(value: &Value) -> Self {
+        let field = |name: &str| value.get(name).and_then(Value::as_str).map(str::to_string);
+        Self {
+            error_type: field("type"),
+            reason: field("reason"),
+            index: field("index"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EsError {
+    pub status: u16,
+    #[serde(rename = "type")]
+    pub error_type: Option<String>,
+    pub reason: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub root_causes: Vec<EsErrorCause>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub caused_by: Option<EsErrorCause>,
+}
+
+impl std::fmt::Display for EsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let json = serde_json::to_string_pretty(self).map_err(|_| std::fmt::Error)?;
+        write!(f, "{}", json)
+    }
+}
+
+impl std::error::Error for EsError {}
+
+impl EsError {
+    /// 从错误响应解析，兼容 {"error": {...}}、{"error": "..."} 以及非 JSON 响应体
+    pub fn from_response(status: u16, body: &str) -> Self {
+        let parsed: Option<Value> = serde_json::from_str(body).ok();
+        let error = parsed.as_ref().and_then(|v| v.get("error"));
+        match error {
+            Some(error @ Value::Object(_)) => {
+                let cause = EsErrorCause::from_value(error);
+                Self {
+                    status,
+                    error_type: cause.error_type,
+                    reason: cause.reason.unwrap_or_else(|| error.to_string()),
+                    root_causes: error
+                        .get("root_cause")
+                        .and_then(Value::as_array)
+                        .map(|causes| causes.iter().map(EsErrorCause::from_value).collect())
+                        .unwrap_or_default(),
+                    caused_by: error.get("caused_by").map(EsErrorCause::from_value),
+                }
+            }
+            Some(Value::String(reason)) => Self::plain(status, reason),
+            _ if body.trim().is_empty() => Self::plain(status, &default_reason(status)),
+            _ => Self::plain(status, body.trim()),
+        }
+    }
+
+    fn plain(status: u16, reason: &str) -> Self {
+        Self {
+            status,
+            error_type: None,
+            reason: reason.to_string(),
+            root_causes: Vec::new(),
+            caused_by: None,
+        }
+    }
+}
+
+fn default_reason(status: u16) -> String {
+    let text = match status {
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        408 => "Request Timeout",
+        409 => "Conflict",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        _ => "HTTP error",
+    };
+    format!("HTTP {} {}", status, text)
+}
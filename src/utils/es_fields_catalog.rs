@@ -0,0 +1,682 @@
+// Elasticsearch 字段目录（elasticsearch-fields.yml）查询：按索引名 / 通配模式定位索引定义，校验字段能力与查询 DSL
+use crate::utils::config::{ElasticsearchField, ElasticsearchFieldsConfig, ElasticsearchIndex};
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::env;
+use wildmatch::WildMatch;
+
+/// 定位索引定义：优先精确匹配，其次目录中的通配名（如 logs-*）匹配请求的索引，或请求的模式匹配目录中的索引
+pub fn find_catalog_index<'a>(config: &'a ElasticsearchFieldsConfig, index: &str) -> Option<&'a ElasticsearchIndex> {
+    config
+        .indices
+        .iter()
+        .find(|idx| idx.name == index)
+        .or_else(|| config.indices.iter().find(|idx| WildMatch::new(&idx.name).matches(index)))
+        .or_else(|| config.indices.iter().find(|idx| WildMatch::new(index).matches(&idx.name)))
+}
+
+pub fn find_field<'a>(index: &'a ElasticsearchIndex, name: &str) -> Option<&'a ElasticsearchField> {
+    index.fields.iter().find(|field| field.name == name)
+}
+
+/// 校验字段可用于聚合；索引不在目录中时跳过校验并返回警告
+pub fn check_aggregatable_fields(index: Option<&ElasticsearchIndex>, fields: &[&str]) -> Result<Vec<String>> {
+    let Some(index) = index else {
+        return Ok(vec!["index is not defined in the fields catalog, fields were not validated".to_string()]);
+    };
+    let mut errors = Vec::new();
+    for name in fields {
+        match find_field(index, name) {
+            None => errors.push(format!("field '{}' is not defined for index '{}'", name, index.name)),
+            Some(field) if !field.is_aggregatable => {
+                errors.push(format!("field '{}' of index '{}' is not aggregatable", name, index.name))
+            }
+            Some(_) => {}
+        }
+    }
+    if !errors.is_empty() {
+        return Err(anyhow::anyhow!("Invalid aggregation fields: {}", errors.join("; ")));
+    }
+    Ok(Vec::new())
+}
+
+/// 默认的查询校验模式：off（不校验）、warn（附带问题后照常执行）、strict（存在错误时不执行）
+pub fn es_query_validation() -> String {
+    env::var("ES_QUERY_VALIDATION").unwrap_or_else(|_| "off".to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QueryValidationMode {
+    Off,
+    Warn,
+    Strict,
+}
+
+impl QueryValidationMode {
+    /// 未指定时使用 ES_QUERY_VALIDATION
+    pub fn resolve(mode: Option<&str>) -> Result<Self> {
+        let mode = mode
+            .map(str::trim)
+            .filter(|m| !m.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(es_query_validation);
+        match mode.to_lowercase().as_str() {
+            "off" | "false" | "none" => Ok(Self::Off),
+            "warn" | "true" => Ok(Self::Warn),
+            "strict" => Ok(Self::Strict),
+            _ => Err(anyhow::anyhow!("Unknown validation mode '{}', expected off, warn or strict", mode)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct QueryValidation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub catalog_index: Option<String>, // 匹配到的目录索引名
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl QueryValidation {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub fn has_issues(&self) -> bool {
+        !self.errors.is_empty() || !self.warnings.is_empty()
+    }
+}
+
+/// 字段名即为子对象 key 的叶子查询，如 {"term": {"status": "ok"}}
+const FIELD_KEYED_CLAUSES: [&str; 12] = [
+    "term",
+    "terms",
+    "match",
+    "match_phrase",
+    "match_phrase_prefix",
+    "match_bool_prefix",
+    "prefix",
+    "wildcard",
+    "regexp",
+    "fuzzy",
+    "range",
+    "terms_set",
+];
+/// 通过 fields / default_field 指定字段的查询
+const FIELD_LIST_CLAUSES: [&str; 3] = ["multi_match", "query_string", "simple_query_string"];
+/// 不引用字段的查询
+const FIELDLESS_CLAUSES: [&str; 3] = ["match_all", "match_none", "ids"];
+/// 叶子查询中不是字段名的 key
+const CLAUSE_OPTION_KEYS: [&str; 2] = ["boost", "_name"];
+
+struct QueryValidator<'a> {
+    index: &'a ElasticsearchIndex,
+    result: QueryValidation,
+}
+
+impl<'a> QueryValidator<'a> {
+    /// 元字段（_id、_index 等）与通配字段不做校验；目录未列出的子字段（如 message.keyword）仅警告
+    fn check_field(&mut self, name: &str, path: &str) -> Option<&'a ElasticsearchField> {
+        if name.starts_with('_') || name.contains('*') {
+            return None;
+        }
+        if let Some(field) = find_field(self.index, name) {
+            return Some(field);
+        }
+        let parent = name.rsplit_once('.').map(|(parent, _)| parent);
+        match parent.filter(|parent| find_field(self.index, parent).is_some()) {
+            Some(parent) => self.result.warnings.push(format!(
+                "{}: field '{}' is not in the catalog of index '{}' (sub-field of '{}')",
+                path, name, self.index.name, parent
+            )),
+            None => self.result.errors.push(format!(
+                "{}: field '{}' is not defined for index '{}'",
+                path, name, self.index.name
+            )),
+        }
+        None
+    }
+
+    fn check_query(&mut self, query: &Value, path: &str) {
+        let Some(clauses) = query.as_object() else {
+            self.result.errors.push(format!("{}: query clause must be a JSON object", path));
+            return;
+        };
+        for (clause, body) in clauses {
+            let path = format!("{}.{}", path, clause);
+            match clause.as_str() {
+                "bool" => {
+                    for occur in ["must", "should", "filter", "must_not"] {
+                        match &body[occur] {
+                            Value::Array(queries) => {
+                                for (i, query) in queries.iter().enumerate() {
+                                    self.check_query(query, &format!("{}.{}[{}]", path, occur, i));
+                                }
+                            }
+                            Value::Object(_) => self.check_query(&body[occur], &format!("{}.{}", path, occur)),
+                            _ => {}
+                        }
+                    }
+                }
+                "constant_score" => self.check_query(&body["filter"], &format!("{}.filter", path)),
+                "function_score" if body.get("query").is_some() => {
+                    self.check_query(&body["query"], &format!("{}.query", path))
+                }
+                "function_score" => {}
+                "boosting" => {
+                    self.check_query(&body["positive"], &format!("{}.positive", path));
+                    self.check_query(&body["negative"], &format!("{}.negative", path));
+                }
+                "dis_max" => {
+                    for (i, query) in body["queries"].as_array().into_iter().flatten().enumerate() {
+                        self.check_query(query, &format!("{}.queries[{}]", path, i));
+                    }
+                }
+                "nested" => {
+                    if let Some(nested_path) = body["path"].as_str() {
+                        self.check_field(nested_path, &path);
+                    }
+                    self.check_query(&body["query"], &format!("{}.query", path));
+                }
+                "exists" => {
+                    if let Some(field) = body["field"].as_str() {
+                        self.check_field(field, &path);
+                    }
+                }
+                clause if FIELD_KEYED_CLAUSES.contains(&clause) => {
+                    for field in body.as_object().into_iter().flat_map(|b| b.keys()) {
+                        if !CLAUSE_OPTION_KEYS.contains(&field.as_str()) {
+                            self.check_field(field, &path);
+                        }
+                    }
+                }
+                clause if FIELD_LIST_CLAUSES.contains(&clause) => {
+                    let fields = body["fields"].as_array().into_iter().flatten().filter_map(Value::as_str);
+                    for field in fields.chain(body["default_field"].as_str()) {
+                        // 去掉权重后缀，如 title^2
+                        let field = field.split('^').next().unwrap_or(field);
+                        self.check_field(field, &path);
+                    }
+                }
+                clause if FIELDLESS_CLAUSES.contains(&clause) => {}
+                _ => self
+                    .result
+                    .warnings
+                    .push(format!("{}: clause '{}' is not validated", path, clause)),
+            }
+        }
+    }
+
+    fn check_sort(&mut self, sort: &Value) {
+        let entries: Vec<&Value> = match sort {
+            Value::Array(entries) => entries.iter().collect(),
+            other => vec![other],
+        };
+        for (i, entry) in entries.into_iter().enumerate() {
+            let path = format!("sort[{}]", i);
+            let fields: Vec<&str> = match entry {
+                Value::String(field) => vec![field.as_str()],
+                Value::Object(map) => map.keys().map(String::as_str).collect(),
+                _ => Vec::new(),
+            };
+            for name in fields {
+                match self.check_field(name, &path) {
+                    Some(field) if !field.is_sortable => self.result.errors.push(format!(
+                        "{}: field '{}' of index '{}' is not sortable",
+                        path, name, self.index.name
+                    )),
+                    Some(field) if field.is_multi_value => self.result.warnings.push(format!(
+                        "{}: field '{}' is multi-valued, documents are sorted by its min/max value",
+                        path, name
+                    )),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn check_aggs(&mut self, aggs: &Value, path: &str) {
+        for (name, definition) in aggs.as_object().into_iter().flatten() {
+            for (agg_type, body) in definition.as_object().into_iter().flatten() {
+                let path = format!("{}.{}.{}", path, name, agg_type);
+                match agg_type.as_str() {
+                    "aggs" | "aggregations" => self.check_aggs(body, &path),
+                    "meta" => {}
+                    "filter" => self.check_query(body, &path),
+                    "filters" => {
+                        for (key, query) in body["filters"].as_object().into_iter().flatten() {
+                            self.check_query(query, &format!("{}.{}", path, key));
+                        }
+                    }
+                    "composite" => {
+                        let sources = body["sources"].as_array().into_iter().flatten();
+                        for source in sources.filter_map(Value::as_object).flat_map(|s| s.values()) {
+                            for field in source.as_object().into_iter().flat_map(|s| s.values()) {
+                                self.check_agg_field(&field["field"], &path);
+                            }
+                        }
+                    }
+                    _ => self.check_agg_field(&body["field"], &path),
+                }
+            }
+        }
+    }
+
+    fn check_agg_field(&mut self, field: &Value, path: &str) {
+        let Some(name) = field.as_str() else {
+            return;
+        };
+        if let Some(field) = self.check_field(name, path).filter(|field| !field.is_aggregatable) {
+            self.result.errors.push(format!(
+                "{}: field '{}' of index '{}' is not aggregatable",
+                path, field.name, self.index.name
+            ));
+        }
+    }
+}
+
+/// 按字段目录校验查询 DSL：query / post_filter 中的字段必须存在，sort 字段需可排序，聚合字段需可聚合
+pub fn validate_search_query(index: Option<&ElasticsearchIndex>, query: &Value) -> QueryValidation {
+    let Some(index) = index else {
+        return QueryValidation {
+            warnings: vec!["index is not defined in the fields catalog, query was not validated".to_string()],
+            ..Default::default()
+        };
+    };
+    let mut validator = QueryValidator {
+        index,
+        result: QueryValidation {
+            catalog_index: Some(index.name.clone()),
+            ..Default::default()
+        },
+    };
+    for section in ["query", "post_filter"] {
+        if let Some(clause) = query.get(section) {
+            validator.check_query(clause, section);
+        }
+    }
+    if let Some(sort) = query.get("sort") {
+        validator.check_sort(sort);
+    }
+    for section in ["aggs", "aggregations"] {
+        if let Some(aggs) = query.get(section) {
+            validator.check_aggs(aggs, section);
+        }
+    }
+    validator.result
+}
+
+/// 默认开启 doc_values、可排序与聚合的字段类型
+const DOC_VALUE_TYPES: [&str; 16] = [
+    "keyword",
+    "constant_keyword",
+    "long",
+    "integer",
+    "short",
+    "byte",
+    "double",
+    "float",
+    "half_float",
+    "scaled_float",
+    "unsigned_long",
+    "date",
+    "date_nanos",
+    "boolean",
+    "ip",
+    "version",
+];
+
+/// 根据字段 mapping 推断 (is_sortable, is_aggregatable)
+fn field_capabilities(field_type: &str, mapping: &Value) -> (bool, bool) {
+    let doc_values = mapping["doc_values"].as_bool().unwrap_or(true);
+    match field_type {
+        t if DOC_VALUE_TYPES.contains(&t) => (doc_values, doc_values),
+        "flattened" => (doc_values, doc_values),
+        // text 只有开启 fielddata 才能排序和聚合
+        "text" | "match_only_text" => {
+            let fielddata = mapping["fielddata"].as_bool().unwrap_or(false);
+            (fielddata, fielddata)
+        }
+        "geo_point" => (false, doc_values),
+        _ => (false, false),
+    }
+}
+
+fn mapping_field(name: String, field_type: &str, mapping: &Value) -> ElasticsearchField {
+    let (is_sortable, is_aggregatable) = field_capabilities(field_type, mapping);
+    ElasticsearchField {
+        name,
+        field_type: Some(field_type.to_string()),
+        description: String::new(),
+        is_sortable,
+        is_aggregatable,
+        is_multi_value: field_type == "nested",
+    }
+}
+
+/// 展开 properties：object / nested 的子字段展开为 a.b，multi-field 展开为 a.keyword；nested 本身保留供 nested 查询引用
+fn collect_mapping_fields(properties: &Value, prefix: &str, out: &mut Vec<ElasticsearchField>) {
+    for (name, mapping) in properties.as_object().into_iter().flatten() {
+        let full_name = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{}.{}", prefix, name)
+        };
+        if let Some(children) = mapping.get("properties") {
+            if mapping["type"] == "nested" {
+                out.push(mapping_field(full_name.clone(), "nested", mapping));
+            }
+            collect_mapping_fields(children, &full_name, out);
+            continue;
+        }
+        let field_type = mapping["type"].as_str().unwrap_or("object");
+        out.push(mapping_field(full_name.clone(), field_type, mapping));
+        for (sub_name, sub_mapping) in mapping["fields"].as_object().into_iter().flatten() {
+            let sub_type = sub_mapping["type"].as_str().unwrap_or("object");
+            out.push(mapping_field(format!("{}.{}", full_name, sub_name), sub_type, sub_mapping));
+        }
+    }
+}
+
+/// 从 get_mapping 的响应（可能包含多个匹配的索引）推断字段，同名字段类型不一致时给出警告并按最保守的能力合并
+pub fn fields_from_mappings(body: &Value) -> (Vec<ElasticsearchField>, Vec<String>) {
+    let mut merged: BTreeMap<String, ElasticsearchField> = BTreeMap::new();
+    let mut warnings = Vec::new();
+    for (index, definition) in body.as_object().into_iter().flatten() {
+        let mut fields = Vec::new();
+        collect_mapping_fields(&definition["mappings"]["properties"], "", &mut fields);
+        for field in fields {
+            match merged.get_mut(&field.name) {
+                Some(existing) => {
+                    if existing.field_type != field.field_type {
+                        warnings.push(format!(
+                            "field '{}' is '{}' in index '{}' but '{}' elsewhere",
+                            field.name,
+                            field.field_type.as_deref().unwrap_or(""),
+                            index,
+                            existing.field_type.as_deref().unwrap_or("")
+                        ));
+                    }
+                    existing.is_sortable &= field.is_sortable;
+                    existing.is_aggregatable &= field.is_aggregatable;
+                }
+                None => {
+                    merged.insert(field.name.clone(), field);
+                }
+            }
+        }
+    }
+    (merged.into_values().collect(), warnings)
+}
+
+/// 合并生成结果与现有目录：保留人工维护的描述与 is_multi_value（mapping 无法体现），已有字段保持原顺序，新字段按名称追加
+pub fn merge_catalog_index(
+    name: &str,
+    existing: Option<&ElasticsearchIndex>,
+    generated: Vec<ElasticsearchField>,
+) -> ElasticsearchIndex {
+    let mut generated: BTreeMap<String, ElasticsearchField> =
+        generated.into_iter().map(|field| (field.name.clone(), field)).collect();
+    let mut fields = Vec::new();
+    for old in existing.map(|index| index.fields.as_slice()).unwrap_or_default() {
+        if let Some(mut field) = generated.remove(&old.name) {
+            field.description = old.description.clone();
+            field.is_multi_value = old.is_multi_value;
+            fields.push(field);
+        }
+    }
+    fields.extend(generated.into_values());
+    ElasticsearchIndex {
+        name: name.to_string(),
+        description: existing.map(|index| index.description.clone()).unwrap_or_default(),
+        fields,
+    }
+}
+
+/// 生成结果相对现有目录的变化说明
+pub fn catalog_changes(existing: Option<&ElasticsearchIndex>, generated: &ElasticsearchIndex) -> Vec<String> {
+    let Some(existing) = existing else {
+        return vec![format!("new index with {} fields", generated.fields.len())];
+    };
+    let mut changes = Vec::new();
+    for old in &existing.fields {
+        if find_field(generated, &old.name).is_none() {
+            changes.push(format!("removed '{}': not present in the mappings", old.name));
+        }
+    }
+    for field in &generated.fields {
+        let Some(old) = find_field(existing, &field.name) else {
+            changes.push(format!(
+                "added '{}' ({}, sortable: {}, aggregatable: {})",
+                field.name,
+                field.field_type.as_deref().unwrap_or("unknown"),
+                field.is_sortable,
+                field.is_aggregatable
+            ));
+            continue;
+        };
+        if old.field_type.is_some() && old.field_type != field.field_type {
+            changes.push(format!(
+                "'{}' type: {} -> {}",
+                field.name,
+                old.field_type.as_deref().unwrap_or(""),
+                field.field_type.as_deref().unwrap_or("")
+            ));
+        }
+        if old.is_sortable != field.is_sortable {
+            changes.push(format!("'{}' is_sortable: {} -> {}", field.name, old.is_sortable, field.is_sortable));
+        }
+        if old.is_aggregatable != field.is_aggregatable {
+            changes.push(format!(
+                "'{}' is_aggregatable: {} -> {}",
+                field.name, old.is_aggregatable, field.is_aggregatable
+            ));
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn field(name: &str, is_sortable: bool, is_aggregatable: bool, is_multi_value: bool) -> ElasticsearchField {
+        ElasticsearchField {
+            name: name.to_string(),
+            field_type: None,
+            description: String::new(),
+            is_sortable,
+            is_aggregatable,
+            is_multi_value,
+        }
+    }
+
+    fn catalog() -> ElasticsearchIndex {
+        ElasticsearchIndex {
+            name: "logs-*".to_string(),
+            description: String::new(),
+            fields: vec![
+                field("@timestamp", true, true, false),
+                field("level", true, true, false),
+                field("message", false, false, false),
+                field("tags", true, true, true),
+                field("user", false, false, false),
+                field("user.name", true, true, false),
+            ],
+        }
+    }
+
+    #[test]
+    fn skips_validation_without_catalog() {
+        let result = validate_search_query(None, &json!({ "query": { "term": { "anything": 1 } } }));
+        assert!(result.is_valid());
+        assert_eq!(result.catalog_index, None);
+        assert_eq!(result.warnings.len(), 1);
+    }
+
+    #[test]
+    fn valid_query_has_no_issues() {
+        let index = catalog();
+        let query = json!({
+            "query": { "bool": {
+                "filter": [{ "term": { "level": "ERROR" } }, { "range": { "@timestamp": { "gte": "now-1h" } } }],
+                "must": { "match": { "message": { "query": "timeout", "boost": 2 } } },
+                "must_not": [{ "exists": { "field": "user.name" } }],
+            } },
+            "post_filter": { "ids": { "values": ["1"] } },
+            "sort": [{ "@timestamp": "desc" }, "_score"],
+            "aggs": { "by_level": { "terms": { "field": "level" }, "aggs": { "last": { "max": { "field": "@timestamp" } } } } },
+        });
+        let result = validate_search_query(Some(&index), &query);
+        assert_eq!(result.catalog_index.as_deref(), Some("logs-*"));
+        assert!(!result.has_issues(), "{:?}", result);
+    }
+
+    #[test]
+    fn reports_unknown_fields_with_path() {
+        let index = catalog();
+        let query = json!({
+            "query": { "bool": { "should": [
+                { "term": { "status": "ok" } },
+                { "multi_match": { "query": "x", "fields": ["message^2", "title"] } },
+            ] } },
+        });
+        let result = validate_search_query(Some(&index), &query);
+        assert_eq!(
+            result.errors,
+            [
+                "query.bool.should[0].term: field 'status' is not defined for index 'logs-*'",
+                "query.bool.should[1].multi_match: field 'title' is not defined for index 'logs-*'",
+            ]
+        );
+    }
+
+    #[test]
+    fn sub_fields_meta_fields_and_unknown_clauses_only_warn() {
+        let index = catalog();
+        let query = json!({
+            "query": { "bool": { "must": [
+                { "term": { "message.keyword": "x" } },
+                { "term": { "_id": "1" } },
+                { "wildcard": { "user.*": "a*" } },
+                { "script": { "script": "true" } },
+            ] } },
+        });
+        let result = validate_search_query(Some(&index), &query);
+        assert!(result.is_valid(), "{:?}", result);
+        assert_eq!(result.warnings.len(), 2);
+        assert!(result.warnings[0].contains("sub-field of 'message'"));
+        assert!(result.warnings[1].contains("clause 'script' is not validated"));
+    }
+
+    #[test]
+    fn checks_sort_and_aggregation_capabilities() {
+        let index = catalog();
+        let query = json!({
+            "sort": [{ "message": "asc" }, "tags"],
+            "aggs": {
+                "by_user": { "terms": { "field": "user" } },
+                "pages": { "composite": { "sources": [{ "lvl": { "terms": { "field": "level" } } }] } },
+                "errors": { "filter": { "term": { "level": "ERROR" } } },
+            },
+        });
+        let result = validate_search_query(Some(&index), &query);
+        assert_eq!(
+            result.errors,
+            [
+                "sort[0]: field 'message' of index 'logs-*' is not sortable",
+                "aggs.by_user.terms: field 'user' of index 'logs-*' is not aggregatable",
+            ]
+        );
+        assert_eq!(
+            result.warnings,
+            ["sort[1]: field 'tags' is multi-valued, documents are sorted by its min/max value"]
+        );
+    }
+
+    #[test]
+    fn non_object_clause_is_an_error() {
+        let index = catalog();
+        let result = validate_search_query(Some(&index), &json!({ "query": { "bool": { "filter": ["level"] } } }));
+        assert_eq!(result.errors, ["query.bool.filter[0]: query clause must be a JSON object"]);
+    }
+
+    fn generated(name: &str) -> ElasticsearchField {
+        let (fields, _) = fields_from_mappings(&mappings());
+        fields.into_iter().find(|f| f.name == name).unwrap()
+    }
+
+    fn mappings() -> Value {
+        json!({
+            "logs-2024.06.01": { "mappings": { "properties": {
+                "@timestamp": { "type": "date" },
+                "message": { "type": "text", "fields": { "keyword": { "type": "keyword", "ignore_above": 256 } } },
+                "status": { "type": "keyword" },
+                "raw": { "type": "keyword", "doc_values": false },
+                "notes": { "type": "text", "fielddata": true },
+                "user": { "properties": { "name": { "type": "keyword" }, "age": { "type": "integer" } } },
+                "items": { "type": "nested", "properties": { "sku": { "type": "keyword" } } },
+                "location": { "type": "geo_point" },
+            } } },
+            "logs-2024.06.02": { "mappings": { "properties": {
+                "@timestamp": { "type": "date" },
+                "status": { "type": "text" },
+            } } },
+        })
+    }
+
+    #[test]
+    fn fields_from_mappings_expands_objects_and_multi_fields() {
+        let (fields, _) = fields_from_mappings(&mappings());
+        let names: Vec<&str> = fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "@timestamp",
+                "items",
+                "items.sku",
+                "location",
+                "message",
+                "message.keyword",
+                "notes",
+                "raw",
+                "status",
+                "user.age",
+                "user.name",
+            ]
+        );
+        let items = generated("items");
+        assert_eq!(items.field_type.as_deref(), Some("nested"));
+        assert!(items.is_multi_value);
+        assert_eq!(generated("user.age").field_type.as_deref(), Some("integer"));
+    }
+
+    #[test]
+    fn fields_from_mappings_infers_capabilities() {
+        let capabilities = |name: &str| {
+            let field = generated(name);
+            (field.is_sortable, field.is_aggregatable)
+        };
+        assert_eq!(capabilities("@timestamp"), (true, true));
+        assert_eq!(capabilities("message"), (false, false));
+        assert_eq!(capabilities("message.keyword"), (true, true));
+        assert_eq!(capabilities("raw"), (false, false));
+        assert_eq!(capabilities("notes"), (true, true));
+        assert_eq!(capabilities("location"), (false, true));
+    }
+
+    #[test]
+    fn fields_from_mappings_merges_conflicting_types_conservatively() {
+        let (fields, warnings) = fields_from_mappings(&mappings());
+        let status = fields.iter().find(|f| f.name == "status").unwrap();
+        assert_eq!(status.field_type.as_deref(), Some("keyword"));
+        assert!(!status.is_sortable && !status.is_aggregatable);
+        assert_eq!(
+            warnings,
+            ["field 'status' is 'text' in index 'logs-2024.06.02' but 'keyword' elsewhere"]
+        );
+    }
+}
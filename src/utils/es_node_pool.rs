@@ -0,0 +1,171 @@
+// Elasticsearch 多节点连接池：轮询选择节点、失败节点临时摘除、可选的节点嗅探
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::env;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// 失败节点被摘除的时长，到期后重新参与轮询
+pub fn es_node_retry_secs() -> u64 {
+    env::var("ES_NODE_RETRY_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(60)
+}
+
+pub fn es_sniff_interval_secs() -> u64 {
+    env::var("ES_SNIFF_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(300)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EsNodeStatus {
+    pub url: String,
+    pub healthy: bool,
+    pub excluded_for_secs: Option<u64>, // 剩余摘除时间
+}
+
+#[derive(Debug)]
+pub struct EsNodePool {
+    seeds: Vec<String>,
+    nodes: RwLock<Vec<String>>,
+    failed: Mutex<HashMap<String, Instant>>, // 节点 -> 恢复时间
+    cursor: AtomicUsize,
+    sniff: bool,
+    last_sniff: Mutex<Option<Instant>>,
+}
+
+fn normalize_url(url: &str) -> String {
+    url.trim().trim_end_matches('/').to_string()
+}
+
+impl EsNodePool {
+    pub fn new(urls: Vec<String>, sniff: bool) -> Self {
+        let seeds: Vec<String> = urls
+            .iter()
+            .map(|url| normalize_url(url))
+            .filter(|url| !url.is_empty())
+            .collect();
+        Self {
+            nodes: RwLock::new(seeds.clone()),
+            seeds,
+            failed: Mutex::new(HashMap::new()),
+            cursor: AtomicUsize::new(0),
+            sniff,
+            last_sniff: Mutex::new(None),
+        }
+    }
+
+    /// 本次请求依次尝试的节点：从轮询游标开始，健康节点在前，摘除中的节点放在最后兜底
+    pub fn candidates(&self) -> Vec<String> {
+        let nodes = self.nodes.read().unwrap().clone();
+        if nodes.is_empty() {
+            return Vec::new();
+        }
+        let start = self.cursor.fetch_add(1, Ordering::Relaxed) % nodes.len();
+        let now = Instant::now();
+        let failed = self.failed.lock().unwrap();
+        let (healthy, excluded): (Vec<String>, Vec<String>) = nodes
+            .iter()
+            .cycle()
+            .skip(start)
+            .take(nodes.len())
+            .cloned()
+            .partition(|node| failed.get(node).is_none_or(|until| *until <= now));
+        healthy.into_iter().chain(excluded).collect()
+    }
+
+    pub fn mark_failed(&self, node: &str) {
+        let until = Instant::now() + Duration::from_secs(es_node_retry_secs());
+        let newly_failed = self.failed.lock().unwrap().insert(node.to_string(), until).is_none();
+        if newly_failed {
+            eprintln!("[Elasticsearch] 节点 {} 请求失败，暂时摘除 {}s", node, es_node_retry_secs());
+        }
+    }
+
+    pub fn mark_healthy(&self, node: &str) {
+        if self.failed.lock().unwrap().remove(node).is_some() {
+            println!("[Elasticsearch] 节点 {} 已恢复", node);
+        }
+    }
+
+    /// 开启嗅探且距上次嗅探超过间隔时返回 true，并记录本次嗅探时间
+    pub fn should_sniff(&self) -> bool {
+        if !self.sniff {
+            return false;
+        }
+        let mut last_sniff = self.last_sniff.lock().unwrap();
+        let due = last_sniff.is_none_or(|at| at.elapsed() >= Duration::from_secs(es_sniff_interval_secs()));
+        if due {
+            *last_sniff = Some(Instant::now());
+        }
+        due
+    }
+
+    /// 用嗅探到的节点替换当前节点列表，结果为空时保留原列表
+    pub fn update_nodes(&self, mut urls: Vec<String>) {
+        urls.sort();
+        urls.dedup();
+        if urls.is_empty() {
+            return;
+        }
+        let mut nodes = self.nodes.write().unwrap();
+        if *nodes != urls {
+            println!("[Elasticsearch] 嗅探到节点: {}", urls.join(", "));
+            *nodes = urls;
+        }
+    }
+
+    /// 嗅探时沿用种子节点的协议
+    pub fn scheme(&self) -> &str {
+        self.seeds
+            .first()
+            .and_then(|url| url.split_once("://"))
+            .map(|(scheme, _)| scheme)
+            .unwrap_or("http")
+    }
+
+    pub fn status(&self) -> Vec<EsNodeStatus> {
+        let now = Instant::now();
+        let failed = self.failed.lock().unwrap();
+        self.nodes
+            .read()
+            .unwrap()
+            .iter()
+            .map(|url| {
+                let remaining = failed.get(url).filter(|until| **until > now).map(|until| (*until - now).as_secs());
+                EsNodeStatus {
+                    url: url.clone(),
+                    healthy: remaining.is_none(),
+                    excluded_for_secs: remaining,
+                }
+            })
+            .collect()
+    }
+}
+
+/// 从 GET /_nodes/http 的响应中解析节点地址，publish_address 可能为 "host/ip:port" 形式
+pub fn parse_sniffed_nodes(body: &Value, scheme: &str) -> Vec<String> {
+    let Some(nodes) = body.get("nodes").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    nodes
+        .values()
+        .filter_map(|node| node.pointer("/http/publish_address").and_then(Value::as_str))
+        .map(|address| {
+            let address = match address.split_once('/') {
+                Some((host, ip_port)) if !host.is_empty() => {
+                    let port = ip_port.rsplit_once(':').map(|(_, port)| port).unwrap_or("9200");
+                    format!("{}:{}", host, port)
+                }
+                Some((_, ip_port)) => ip_port.to_string(),
+                None => address.to_string(),
+            };
+            format!("{}://{}", scheme, address)
+        })
+        .collect()
+}
@@ -0,0 +1,359 @@
+// Elasticsearch 深度分页会话：ES 7.10+ / OpenSearch 2.4+ 使用 PIT + search_after，其余版本使用 scroll，会话以不透明令牌保存在服务端
+use crate::utils::es_search::hits_within_budget;
+use crate::utils::es_version::EsVersion;
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::env;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 分页上下文（PIT / scroll）与令牌的保活时间，每取一页刷新一次
+pub fn es_page_keep_alive_secs() -> u64 {
+    env::var("ES_PAGE_KEEP_ALIVE_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(300)
+}
+
+/// 传给 Elasticsearch 的 keep_alive / scroll 参数
+pub fn keep_alive_param() -> String {
+    format!("{}s", es_page_keep_alive_secs())
+}
+
+#[derive(Debug, Clone)]
+pub enum PageCursor {
+    Pit {
+        pit_id: String,
+        search_after: Option<Value>,
+    },
+    Scroll {
+        scroll_id: Option<String>, // 首页请求前为 None
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct PageSession {
+    pub config_name: String,
+    pub index: String,
+    pub body: Value, // 查询模板（query / sort / size / _source）
+    pub cursor: PageCursor,
+    pub page_size: u64,
+    pub pages: u32,
+    pub returned_hits: u64,
+}
+
+/// 分页结果中除命中外的元数据（令牌、页码等）预留的字节数
+pub const PAGE_META_RESERVE_BYTES: usize = 512;
+
+/// 补默认排序：PIT 且支持 _shard_doc 时用 _shard_doc（最省资源，ES 会自动附加为 tiebreaker）；
+/// 其余 PIT 版本以 _id 作为 tiebreaker，保证 search_after 的排序值唯一；scroll 使用 _doc
+pub fn apply_default_sort(body: &mut Value, version: &EsVersion) {
+    if !version.supports_pit() {
+        if body.get("sort").is_none() {
+            body["sort"] = json!(["_doc"]);
+        }
+        return;
+    }
+    if version.supports_shard_doc_sort() {
+        if body.get("sort").is_none() {
+            body["sort"] = json!([{ "_shard_doc": "asc" }]);
+        }
+        return;
+    }
+    let mut sort = match body.get("sort").cloned() {
+        Some(Value::Array(sort)) => sort,
+        Some(sort) => vec![sort],
+        None => vec![json!({ "_doc": "asc" })],
+    };
+    let has_id = sort.iter().any(|s| s.as_str() == Some("_id") || s.get("_id").is_some());
+    if !has_id {
+        sort.push(json!({ "_id": "asc" }));
+    }
+    body["sort"] = Value::Array(sort);
+}
+
+/// 一页结果：返回给调用方的 JSON，以及分页是否已结束
+#[derive(Debug, Clone)]
+pub struct PageResult {
+    pub page: Value,
+    pub finished: bool,
+}
+
+/// 单条命中就超出预算时去掉文档内容，只保留 _id / sort 等定位信息
+fn strip_hit_body(hit: &mut Value) {
+    if let Some(hit) = hit.as_object_mut() {
+        for key in ["_source", "fields", "highlight", "inner_hits"] {
+            hit.remove(key);
+        }
+        hit.insert("_source_omitted".to_string(), Value::Bool(true));
+    }
+}
+
+fn page_hits(hits: &[Value], compact: bool) -> Value {
+    if !compact {
+        return Value::Array(hits.to_vec());
+    }
+    hits.iter()
+        .map(|hit| match hit.get("_source") {
+            Some(source) => source.clone(),
+            None if hit.get("_source_omitted").is_some() => json!({ "_id": hit["_id"], "_source_omitted": true }),
+            None => Value::Null,
+        })
+        .collect()
+}
+
+/// 按字节预算组装一页结果并推进游标：
+/// - PIT 超出预算时只返回前 kept 条，从最后返回的命中继续，剩余命中留到下一页；
+///   单条命中就超出预算时返回去掉内容的首条，保证 search_after 前进
+/// - scroll 无法回退，超出预算直接报错，由调用方释放会话
+pub fn build_page(session: &mut PageSession, response: &Value, compact: bool, budget: usize) -> Result<PageResult> {
+    let mut hits: Vec<Value> = response
+        .pointer("/hits/hits")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    let fetched = hits.len();
+    let mut page = json!({
+        "total": response.pointer("/hits/total").cloned().unwrap_or(Value::Null),
+        "hits": page_hits(&hits, compact),
+    });
+
+    let kept = hits_within_budget(&page, true, budget.saturating_sub(PAGE_META_RESERVE_BYTES))?.min(fetched);
+    let mut returned = fetched;
+    if kept < fetched {
+        if !matches!(session.cursor, PageCursor::Pit { .. }) {
+            return Err(anyhow::anyhow!(
+                "Page of {} hits exceeds {} bytes (only {} fit) and a scroll cannot resume mid-page; \
+                 start again with compact=true, _source filtering in the query or page_size <= {}",
+                fetched,
+                budget,
+                kept,
+                kept.max(1)
+            ));
+        }
+        returned = kept.max(1);
+        hits.truncate(returned);
+        if kept == 0 {
+            strip_hit_body(&mut hits[0]);
+        }
+        page["hits"] = page_hits(&hits, compact);
+        page["_truncated"] = json!({
+            "reason": format!("page exceeded {} bytes", budget),
+            "returned_hits": returned,
+            "omitted_hits": fetched - returned,
+            "deferred_to_next_page": true,
+            "hint": "use compact mode, _source filtering in the query or a smaller page_size",
+        });
+        if kept == 0 {
+            page["_truncated"]["source_omitted"] = json!(true);
+        }
+    }
+    if let PageCursor::Pit { search_after, .. } = &mut session.cursor {
+        if let Some(sort) = hits.last().and_then(|hit| hit.get("sort")) {
+            *search_after = Some(sort.clone());
+        }
+    }
+
+    session.pages += 1;
+    session.returned_hits += returned as u64;
+    page["page"] = json!(session.pages);
+    page["returned_hits"] = json!(session.returned_hits);
+    Ok(PageResult {
+        page,
+        finished: (fetched as u64) < session.page_size && returned == fetched,
+    })
+}
+
+static PAGE_SESSIONS: Lazy<Mutex<HashMap<String, (PageSession, Instant)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+static TOKEN_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+pub fn new_page_token() -> String {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(TOKEN_COUNTER.fetch_add(1, Ordering::Relaxed));
+    format!("es-page-{:016x}", hasher.finish())
+}
+
+/// 保存（或续期）会话，返回有效期秒数
+pub fn save_session(token: &str, session: PageSession) -> u64 {
+    let ttl = es_page_keep_alive_secs();
+    PAGE_SESSIONS
+        .lock()
+        .unwrap()
+        .insert(token.to_string(), (session, Instant::now() + Duration::from_secs(ttl)));
+    ttl
+}
+
+/// 取出会话，取出期间其他请求无法使用同一令牌
+pub fn take_session(token: &str) -> Result<PageSession> {
+    let (session, expires_at) = PAGE_SESSIONS
+        .lock()
+        .unwrap()
+        .remove(token)
+        .ok_or_else(|| anyhow::anyhow!("Unknown or already finished continuation token '{}'", token))?;
+    if expires_at <= Instant::now() {
+        return Err(anyhow::anyhow!(
+            "Continuation token '{}' has expired, start a new search without a token",
+            token
+        ));
+    }
+    Ok(session)
+}
+
+/// 移除并返回已过期的会话，由调用方释放对应的 PIT / scroll 上下文
+pub fn take_expired_sessions() -> Vec<PageSession> {
+    let now = Instant::now();
+    let mut sessions = PAGE_SESSIONS.lock().unwrap();
+    let expired: Vec<String> = sessions
+        .iter()
+        .filter(|(_, (_, expires_at))| *expires_at <= now)
+        .map(|(token, _)| token.clone())
+        .collect();
+    expired
+        .into_iter()
+        .filter_map(|token| sessions.remove(&token).map(|(session, _)| session))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(body: Value, version: &str) -> Value {
+        let mut body = body;
+        apply_default_sort(&mut body, &EsVersion::from_config(version).unwrap());
+        body["sort"].clone()
+    }
+
+    #[test]
+    fn default_sort_by_version() {
+        assert_eq!(sorted(json!({}), "8.11"), json!([{ "_shard_doc": "asc" }]));
+        assert_eq!(sorted(json!({}), "7.10"), json!([{ "_doc": "asc" }, { "_id": "asc" }]));
+        assert_eq!(sorted(json!({}), "opensearch-2.11"), json!([{ "_doc": "asc" }, { "_id": "asc" }]));
+        assert_eq!(sorted(json!({}), "7.9"), json!(["_doc"]));
+        assert_eq!(sorted(json!({}), "opensearch-1.3"), json!(["_doc"]));
+    }
+
+    #[test]
+    fn user_sort_gets_id_tiebreaker_without_shard_doc() {
+        let sort = json!({ "@timestamp": "desc" });
+        assert_eq!(sorted(json!({ "sort": sort }), "8.11"), json!({ "@timestamp": "desc" }));
+        assert_eq!(
+            sorted(json!({ "sort": sort }), "opensearch-2.11"),
+            json!([{ "@timestamp": "desc" }, { "_id": "asc" }])
+        );
+        assert_eq!(
+            sorted(json!({ "sort": ["@timestamp", { "_id": "desc" }] }), "7.10"),
+            json!(["@timestamp", { "_id": "desc" }])
+        );
+    }
+
+    fn session(cursor: PageCursor, page_size: u64) -> PageSession {
+        PageSession {
+            config_name: "es".to_string(),
+            index: "logs".to_string(),
+            body: json!({ "query": { "match_all": {} } }),
+            cursor,
+            page_size,
+            pages: 0,
+            returned_hits: 0,
+        }
+    }
+
+    fn pit() -> PageCursor {
+        PageCursor::Pit {
+            pit_id: "pit".to_string(),
+            search_after: None,
+        }
+    }
+
+    fn response(sizes: &[usize]) -> Value {
+        let hits: Vec<Value> = sizes
+            .iter()
+            .enumerate()
+            .map(|(i, size)| json!({ "_id": i.to_string(), "_source": { "message": "x".repeat(*size) }, "sort": [i] }))
+            .collect();
+        json!({ "hits": { "total": { "value": hits.len(), "relation": "eq" }, "hits": hits } })
+    }
+
+    fn search_after(session: &PageSession) -> Option<Value> {
+        match &session.cursor {
+            PageCursor::Pit { search_after, .. } => search_after.clone(),
+            PageCursor::Scroll { .. } => None,
+        }
+    }
+
+    #[test]
+    fn session_store_round_trip() {
+        let token = new_page_token();
+        assert_ne!(token, new_page_token());
+        save_session(&token, session(pit(), 10));
+        assert_eq!(take_session(&token).unwrap().index, "logs");
+        // 取出期间令牌不可再用
+        assert!(take_session(&token).is_err());
+        assert!(take_session("es-page-unknown").is_err());
+    }
+
+    #[test]
+    fn pit_page_within_budget() {
+        let mut session = session(pit(), 2);
+        let result = build_page(&mut session, &response(&[10, 10]), false, 100_000).unwrap();
+        assert_eq!(result.page["hits"].as_array().unwrap().len(), 2);
+        assert!(result.page.get("_truncated").is_none());
+        assert!(!result.finished);
+        assert_eq!(search_after(&session), Some(json!([1])));
+        assert_eq!((session.pages, session.returned_hits), (1, 2));
+
+        let result = build_page(&mut session, &response(&[10]), true, 100_000).unwrap();
+        assert_eq!(result.page["hits"], json!([{ "message": "x".repeat(10) }]));
+        assert!(result.finished);
+    }
+
+    #[test]
+    fn pit_defers_hits_over_budget() {
+        let mut session = session(pit(), 3);
+        let budget = PAGE_META_RESERVE_BYTES + 1500;
+        let result = build_page(&mut session, &response(&[600, 600, 600]), false, budget).unwrap();
+        let returned = result.page["hits"].as_array().unwrap().len();
+        assert!(returned > 0 && returned < 3);
+        assert_eq!(result.page["_truncated"]["deferred_to_next_page"], json!(true));
+        assert_eq!(search_after(&session), Some(json!([returned - 1])));
+        assert!(!result.finished);
+    }
+
+    #[test]
+    fn pit_oversized_single_hit_still_advances() {
+        let mut session = session(pit(), 5);
+        let result = build_page(&mut session, &response(&[5000]), false, PAGE_META_RESERVE_BYTES + 1000).unwrap();
+        let hits = result.page["hits"].as_array().unwrap();
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].get("_source").is_none());
+        assert_eq!(hits[0]["_source_omitted"], json!(true));
+        assert_eq!(result.page["_truncated"]["source_omitted"], json!(true));
+        assert_eq!(search_after(&session), Some(json!([0])));
+        assert_eq!(session.returned_hits, 1);
+
+        let mut session = self::session(pit(), 5);
+        let result = build_page(&mut session, &response(&[5000, 5000]), true, PAGE_META_RESERVE_BYTES + 1000).unwrap();
+        assert_eq!(result.page["hits"], json!([{ "_id": "0", "_source_omitted": true }]));
+        assert_eq!(result.page["_truncated"]["omitted_hits"], json!(1));
+        assert!(!result.finished);
+    }
+
+    #[test]
+    fn scroll_page_over_budget_fails() {
+        let mut session = session(PageCursor::Scroll { scroll_id: Some("s".to_string()) }, 3);
+        let error = build_page(&mut session, &response(&[600, 600, 600]), false, PAGE_META_RESERVE_BYTES + 1500)
+            .unwrap_err();
+        assert!(error.to_string().contains("scroll cannot resume mid-page"));
+        assert_eq!(session.pages, 0);
+
+        let result = build_page(&mut session, &response(&[10]), false, 100_000).unwrap();
+        assert!(result.finished);
+    }
+}
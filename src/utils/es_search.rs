@@ -0,0 +1,125 @@
+// Elasticsearch 搜索结果整形：限制 size、_source 过滤、精简模式与按字节预算截断
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::env;
+
+/// 单次搜索允许的最大 size，超过时强制截断
+pub fn es_max_search_size() -> u64 {
+    env::var("ES_MAX_SEARCH_SIZE")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(100)
+}
+
+/// 返回给客户端的响应最大字节数
+pub fn es_max_response_bytes() -> usize {
+    env::var("ES_MAX_RESPONSE_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(100_000)
+}
+
+/// 强制 size 上限并设置 _source 过滤，返回对查询所做调整的说明
+pub fn prepare_search_query(
+    query: &mut Value,
+    source_includes: &[String],
+    source_excludes: &[String],
+) -> Result<Vec<String>> {
+    let body = query
+        .as_object_mut()
+        .ok_or_else(|| anyhow::anyhow!("Search query must be a JSON object"))?;
+    let mut notes = Vec::new();
+
+    let max_size = es_max_search_size();
+    match body.get("size").map(|size| size.as_u64()) {
+        Some(Some(size)) if size > max_size => {
+            notes.push(format!("size {} reduced to server maximum {}", size, max_size));
+            body.insert("size".to_string(), json!(max_size));
+        }
+        Some(None) => return Err(anyhow::anyhow!("Search query size must be a non-negative integer")),
+        _ => {}
+    }
+
+    if !source_includes.is_empty() || !source_excludes.is_empty() {
+        body.insert(
+            "_source".to_string(),
+            json!({ "includes": source_includes, "excludes": source_excludes }),
+        );
+    }
+    Ok(notes)
+}
+
+/// 精简模式：只保留命中文档的 _source、总数与聚合结果
+pub fn compact_search_response(body: &Value) -> Value {
+    let hits: Vec<Value> = body
+        .pointer("/hits/hits")
+        .and_then(Value::as_array)
+        .map(|hits| hits.iter().map(|hit| hit.get("_source").cloned().unwrap_or(Value::Null)).collect())
+        .unwrap_or_default();
+    let mut compact = json!({
+        "total": body.pointer("/hits/total").cloned().unwrap_or(Value::Null),
+        "hits": hits,
+    });
+    if let Some(aggregations) = body.get("aggregations") {
+        compact["aggregations"] = aggregations.clone();
+    }
+    compact
+}
+
+fn hits_mut(body: &mut Value, compact: bool) -> Option<&mut Vec<Value>> {
+    let pointer = if compact { "/hits" } else { "/hits/hits" };
+    body.pointer_mut(pointer).and_then(Value::as_array_mut)
+}
+
+/// 按字节预算输出：超出时从末尾丢弃命中文档并标注 _truncated，仍超出时按字节截断文本并追加标记
+pub fn render_with_budget(body: Value, compact: bool, budget: usize) -> Result<String> {
+    Ok(render_budgeted(body, compact, budget)?.0)
+}
+
+/// 预算内最多能保留的命中数，供分页时把未返回的命中留给下一页
+pub fn hits_within_budget(body: &Value, compact: bool, budget: usize) -> Result<usize> {
+    Ok(render_budgeted(body.clone(), compact, budget)?.1)
+}
+
+fn render_budgeted(mut body: Value, compact: bool, budget: usize) -> Result<(String, usize)> {
+    let total_hits = hits_mut(&mut body, compact).map(|hits| hits.len()).unwrap_or(0);
+    let text = serde_json::to_string_pretty(&body)?;
+    if text.len() <= budget {
+        return Ok((text, total_hits));
+    }
+
+    let original_bytes = text.len();
+    let mut kept = total_hits;
+    while kept > 0 {
+        // 按比例估算保留数量，避免逐条重试
+        let ratio = budget as f64 / serde_json::to_string_pretty(&body)?.len() as f64;
+        kept = ((kept as f64 * ratio) as usize).min(kept - 1);
+        if let Some(hits) = hits_mut(&mut body, compact) {
+            hits.truncate(kept);
+        }
+        body["_truncated"] = json!({
+            "reason": format!("response exceeded {} bytes ({} bytes)", budget, original_bytes),
+            "returned_hits": kept,
+            "omitted_hits": total_hits - kept,
+            "hint": "use compact mode, _source includes/excludes, a smaller size or ESSearchPage",
+        });
+        let text = serde_json::to_string_pretty(&body)?;
+        if text.len() <= budget {
+            return Ok((text, kept));
+        }
+    }
+
+    // 聚合等非命中部分仍然超出预算，按字符边界截断文本
+    let text = serde_json::to_string_pretty(&body)?;
+    let mut cut = budget.min(text.len());
+    while !text.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    let text = format!(
+        "{}\n...[truncated: response is {} bytes, budget is {} bytes]",
+        &text[..cut],
+        original_bytes,
+        budget
+    );
+    Ok((text, kept))
+}
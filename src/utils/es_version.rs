@@ -0,0 +1,190 @@
+// Elasticsearch / OpenSearch 版本识别，以及不同版本间响应结构的归一化
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EsDistribution {
+    Elasticsearch,
+    OpenSearch,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EsVersion {
+    pub distribution: EsDistribution,
+    pub number: String,
+    pub major: u32,
+}
+
+impl EsVersion {
+    pub fn new(distribution: EsDistribution, number: &str) -> Result<Self> {
+        let major = number
+            .split('.')
+            .next()
+            .and_then(|m| m.trim().parse::<u32>().ok())
+            .ok_or_else(|| anyhow::anyhow!("Invalid Elasticsearch version '{}'", number))?;
+        let version = Self {
+            distribution,
+            number: number.to_string(),
+            major,
+        };
+        if !version.is_supported() {
+            return Err(anyhow::anyhow!(
+                "Unsupported {} version {}, supported: Elasticsearch 6.x/7.x/8.x, OpenSearch 1.x/2.x",
+                version.distribution_name(),
+                number
+            ));
+        }
+        Ok(version)
+    }
+
+    /// 解析配置中的版本，如 "6.8"、"7.17"、"8.0"、"opensearch-2.11"、"opensearch 1"
+    pub fn from_config(version: &str) -> Result<Self> {
+        let lower = version.trim().to_lowercase();
+        match lower.strip_prefix("opensearch") {
+            Some(rest) => Self::new(EsDistribution::OpenSearch, rest.trim_start_matches(['-', ' ', '_'])),
+            None => Self::new(EsDistribution::Elasticsearch, &lower),
+        }
+    }
+
+    /// 根据根路径（GET /）响应识别版本，OpenSearch 会返回 version.distribution = "opensearch"
+    pub fn from_root_response(body: &Value) -> Result<Self> {
+        let number = body["version"]["number"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Root endpoint response has no version.number: {}", body))?;
+        let distribution = match body["version"]["distribution"].as_str() {
+            Some("opensearch") => EsDistribution::OpenSearch,
+            _ => EsDistribution::Elasticsearch,
+        };
+        Self::new(distribution, number)
+    }
+
+    pub fn distribution_name(&self) -> &'static str {
+        match self.distribution {
+            EsDistribution::Elasticsearch => "Elasticsearch",
+            EsDistribution::OpenSearch => "OpenSearch",
+        }
+    }
+
+    pub fn is_supported(&self) -> bool {
+        match self.distribution {
+            EsDistribution::Elasticsearch => (6..=8).contains(&self.major),
+            EsDistribution::OpenSearch => (1..=2).contains(&self.major),
+        }
+    }
+
+    /// 仅 Elasticsearch 8.x 使用官方客户端，其余版本使用通用 HTTP 客户端
+    pub fn uses_official_client(&self) -> bool {
+        self.distribution == EsDistribution::Elasticsearch && self.major >= 8
+    }
+
+    fn minor(&self) -> u32 {
+        self.number
+            .split('.')
+            .nth(1)
+            .and_then(|m| m.trim().parse::<u32>().ok())
+            .unwrap_or(0)
+    }
+
+    fn at_least(&self, major: u32, minor: u32) -> bool {
+        self.major > major || (self.major == major && self.minor() >= minor)
+    }
+
+    /// 深度分页方式：Elasticsearch 7.10+ 与 OpenSearch 2.4+ 使用 PIT + search_after，其余版本使用 scroll
+    pub fn supports_pit(&self) -> bool {
+        match self.distribution {
+            EsDistribution::Elasticsearch => self.at_least(7, 10),
+            EsDistribution::OpenSearch => self.at_least(2, 4),
+        }
+    }
+
+    /// PIT 的 _shard_doc 排序（及隐式 tiebreaker）自 Elasticsearch 7.12 起提供，OpenSearch 没有
+    pub fn supports_shard_doc_sort(&self) -> bool {
+        self.distribution == EsDistribution::Elasticsearch && self.at_least(7, 12)
+    }
+
+    /// date_histogram 自 Elasticsearch 7.2 起使用 fixed_interval / calendar_interval，之前只有 interval
+    pub fn supports_split_intervals(&self) -> bool {
+        match self.distribution {
+            EsDistribution::Elasticsearch => self.at_least(7, 2),
+            EsDistribution::OpenSearch => true,
+        }
+    }
+
+    /// 6.x 的 mapping 带 type 层级（如 _doc），hits.total 为数字
+    pub fn has_mapping_types(&self) -> bool {
+        self.distribution == EsDistribution::Elasticsearch && self.major < 7
+    }
+}
+
+impl std::fmt::Display for EsVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.distribution_name(), self.number)
+    }
+}
+
+/// 去掉 6.x mapping 中的 type 层级，统一为 {"properties": ...} 结构
+fn strip_mapping_type(mappings: &mut Value) {
+    let Some(map) = mappings.as_object() else {
+        return;
+    };
+    if map.contains_key("properties") || map.len() != 1 {
+        return;
+    }
+    if let Some(typed) = map.values().next().filter(|t| t.get("properties").is_some()).cloned() {
+        *mappings = typed;
+    }
+}
+
+/// 归一化 GET /index 或 GET /index/_mapping 的响应：{index: {mappings: {properties}}}
+pub fn normalize_mappings(mut body: Value, version: &EsVersion) -> Value {
+    if !version.has_mapping_types() {
+        return body;
+    }
+    if let Some(indices) = body.as_object_mut() {
+        for index in indices.values_mut() {
+            if let Some(mappings) = index.get_mut("mappings") {
+                strip_mapping_type(mappings);
+            }
+        }
+    }
+    body
+}
+
+/// 归一化搜索响应：6.x 的 hits.total 数字转为 {"value": n, "relation": "eq"}
+pub fn normalize_search_response(mut body: Value) -> Value {
+    if let Some(total) = body.pointer_mut("/hits/total") {
+        if let Some(count) = total.as_u64() {
+            *total = json!({ "value": count, "relation": "eq" });
+        }
+    }
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(spec: &str) -> EsVersion {
+        EsVersion::from_config(spec).unwrap()
+    }
+
+    #[test]
+    fn pit_support_by_version() {
+        for spec in ["7.10.2", "7.17", "8.11.0", "opensearch-2.4", "opensearch 2.11.1"] {
+            assert!(version(spec).supports_pit(), "{}", spec);
+        }
+        for spec in ["6.8", "7.9.3", "opensearch-1.3", "opensearch-2.3"] {
+            assert!(!version(spec).supports_pit(), "{}", spec);
+        }
+    }
+
+    #[test]
+    fn shard_doc_sort_only_on_elasticsearch_7_12_plus() {
+        assert!(version("7.12.0").supports_shard_doc_sort());
+        assert!(version("8.0").supports_shard_doc_sort());
+        assert!(!version("7.10").supports_shard_doc_sort());
+        assert!(!version("opensearch-2.11").supports_shard_doc_sort());
+    }
+}
@@ -0,0 +1,88 @@
+// 离线模式下的本地配置文件监听，文件变更后复用 Nacos 热加载的解析逻辑
+use crate::utils::nacos_config::{
+    get_config_file, get_es_fields_file, read_config_from_file, read_es_fields_config_from_file,
+    reload_config_from_str, reload_es_fields_config_from_str,
+};
+use anyhow::Result;
+use notify_debouncer_mini::{new_debouncer, notify::RecursiveMode, DebounceEventResult};
+use std::collections::HashSet;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+pub fn config_watch_debounce_ms() -> u64 {
+    env::var("CONFIG_WATCH_DEBOUNCE_MS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(500)
+}
+
+/// 监听 CONFIG_FILE 与 ES_FIELDS_FILE，变更经防抖后重新加载
+pub fn watch_offline_config_files() -> Result<()> {
+    let config_path = canonical_path(&get_config_file())?;
+    let es_fields_path = canonical_path(&get_es_fields_file())?;
+
+    // 监听父目录而不是文件本身，兼容编辑器"写临时文件再重命名"的保存方式
+    let mut dirs = HashSet::new();
+    for path in [&config_path, &es_fields_path] {
+        if let Some(parent) = path.parent() {
+            dirs.insert(parent.to_path_buf());
+        }
+    }
+
+    let (tx, rx) = mpsc::channel::<DebounceEventResult>();
+    let mut debouncer = new_debouncer(Duration::from_millis(config_watch_debounce_ms()), tx)?;
+    for dir in &dirs {
+        debouncer.watcher().watch(dir, RecursiveMode::NonRecursive)?;
+    }
+
+    thread::Builder::new()
+        .name("config-file-watcher".to_string())
+        .spawn(move || {
+            // debouncer 需要与监听线程同生命周期，drop 后监听即停止
+            let _debouncer = debouncer;
+            for result in rx {
+                match result {
+                    Ok(events) => {
+                        let changed: HashSet<&Path> = events.iter().map(|e| e.path.as_path()).collect();
+                        if changed.contains(config_path.as_path()) {
+                            reload_main_config_file();
+                        }
+                        if changed.contains(es_fields_path.as_path()) {
+                            reload_es_fields_config_file();
+                        }
+                    }
+                    Err(e) => eprintln!("[Config] 配置文件监听出错: {e}"),
+                }
+            }
+        })?;
+
+    println!(
+        "[Config] 已开启本地配置文件监听: {}, {}",
+        get_config_file(),
+        get_es_fields_file()
+    );
+    Ok(())
+}
+
+fn reload_main_config_file() {
+    let result = read_config_from_file().and_then(|content| reload_config_from_str(&content));
+    if let Err(e) = result {
+        eprintln!("[Config] 主配置文件热加载失败，继续使用旧配置: {e}");
+    }
+}
+
+fn reload_es_fields_config_file() {
+    let result = read_es_fields_config_from_file()
+        .and_then(|content| reload_es_fields_config_from_str(&content));
+    if let Err(e) = result {
+        eprintln!("[Config] ES字段配置文件热加载失败，继续使用旧配置: {e}");
+    }
+}
+
+fn canonical_path(path: &str) -> Result<PathBuf> {
+    std::fs::canonicalize(path)
+        .map_err(|e| anyhow::anyhow!("无法解析配置文件路径 {}: {}", path, e))
+}
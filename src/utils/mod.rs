@@ -1,3 +1,21 @@
 pub mod date_util;
 pub mod nacos_config;
 pub mod config;
+pub mod file_watcher;
+pub mod config_snapshot;
+pub mod nacos_client;
+pub mod nacos_open_api;
+pub mod config_format;
+pub mod nacos_naming;
+pub mod nacos_publish;
+pub mod sls_client;
+pub mod sls_query;
+pub mod aliyun_cli;
+pub mod cli_runner;
+pub mod es_version;
+pub mod es_node_pool;
+pub mod es_error;
+pub mod es_search;
+pub mod es_pagination;
+pub mod es_fields_catalog;
+pub mod es_aggregation;
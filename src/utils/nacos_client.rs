@@ -0,0 +1,197 @@
+// Nacos 集群解析与客户端构建：支持多集群以及用户名密码、AK/SK 鉴权
+use crate::utils::config::NacosClusterConfig;
+use crate::utils::nacos_config::{get_config_inner, nacos_server_addr};
+use anyhow::Result;
+use nacos_sdk::api::config::{ConfigService, ConfigServiceBuilder};
+use nacos_sdk::api::naming::{NamingService, NamingServiceBuilder};
+use nacos_sdk::api::props::ClientProps;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Mutex, Once};
+use std::time::{Duration, Instant};
+
+/// 未指定集群时使用的集群名，对应 NACOS_SERVER_ADDR 等环境变量
+pub const DEFAULT_NACOS_CLUSTER: &str = "default";
+
+fn non_empty_env(key: &str) -> Option<String> {
+    env::var(key).ok().filter(|v| !v.is_empty())
+}
+
+/// 由环境变量构建的默认集群，也是加载主配置所用的集群
+pub fn default_nacos_cluster() -> NacosClusterConfig {
+    NacosClusterConfig {
+        name: DEFAULT_NACOS_CLUSTER.to_string(),
+        server_addr: nacos_server_addr(),
+        username: non_empty_env("NACOS_USERNAME"),
+        password: non_empty_env("NACOS_PASSWORD"),
+        access_key: non_empty_env("NACOS_ACCESS_KEY"),
+        secret_key: non_empty_env("NACOS_SECRET_KEY"),
+        description: "Nacos cluster from NACOS_SERVER_ADDR".to_string(),
+    }
+}
+
+/// 按名称查找集群；配置中未声明 default 时回退到环境变量
+pub fn resolve_nacos_cluster(cluster: Option<&str>) -> Result<NacosClusterConfig> {
+    let name = cluster
+        .filter(|c| !c.is_empty())
+        .unwrap_or(DEFAULT_NACOS_CLUSTER);
+    if let Ok(config) = get_config_inner() {
+        if let Some(found) = config.nacos_clusters.iter().find(|c| c.name == name) {
+            return Ok(found.clone());
+        }
+    }
+    if name == DEFAULT_NACOS_CLUSTER {
+        return Ok(default_nacos_cluster());
+    }
+    Err(anyhow::anyhow!("Nacos cluster '{}' not found", name))
+}
+
+/// 集群使用的鉴权方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NacosAuthMode {
+    None,
+    UsernamePassword,
+    AccessKey,
+}
+
+impl NacosAuthMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::UsernamePassword => "username_password",
+            Self::AccessKey => "access_key",
+        }
+    }
+}
+
+pub fn nacos_auth_mode(cluster: &NacosClusterConfig) -> NacosAuthMode {
+    if cluster.access_key.is_some() && cluster.secret_key.is_some() {
+        NacosAuthMode::AccessKey
+    } else if cluster.username.is_some() && cluster.password.is_some() {
+        NacosAuthMode::UsernamePassword
+    } else {
+        NacosAuthMode::None
+    }
+}
+
+fn client_props(cluster: &NacosClusterConfig, namespace: &str) -> ClientProps {
+    let props = ClientProps::new()
+        .server_addr(&cluster.server_addr)
+        .namespace(namespace)
+        .app_name("mcp-server");
+    match nacos_auth_mode(cluster) {
+        NacosAuthMode::AccessKey => props
+            .auth_access_key(cluster.access_key.clone().unwrap_or_default())
+            .auth_access_secret(cluster.secret_key.clone().unwrap_or_default()),
+        NacosAuthMode::UsernamePassword => props
+            .auth_username(cluster.username.clone().unwrap_or_default())
+            .auth_password(cluster.password.clone().unwrap_or_default()),
+        NacosAuthMode::None => props,
+    }
+}
+
+pub fn build_config_service(cluster: &NacosClusterConfig, namespace: &str) -> Result<ConfigService> {
+    let builder = ConfigServiceBuilder::new(client_props(cluster, namespace));
+    let builder = match nacos_auth_mode(cluster) {
+        NacosAuthMode::AccessKey => builder.enable_auth_plugin_aliyun(),
+        NacosAuthMode::UsernamePassword => builder.enable_auth_plugin_http(),
+        NacosAuthMode::None => builder,
+    };
+    Ok(builder.build()?)
+}
+
+pub fn build_naming_service(cluster: &NacosClusterConfig, namespace: &str) -> Result<NamingService> {
+    let builder = NamingServiceBuilder::new(client_props(cluster, namespace));
+    let builder = match nacos_auth_mode(cluster) {
+        NacosAuthMode::AccessKey => builder.enable_auth_plugin_aliyun(),
+        NacosAuthMode::UsernamePassword => builder.enable_auth_plugin_http(),
+        NacosAuthMode::None => builder,
+    };
+    Ok(builder.build()?)
+}
+
+pub fn nacos_client_idle_secs() -> u64 {
+    env::var("NACOS_CLIENT_IDLE_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(600)
+}
+
+const EVICTION_INTERVAL: Duration = Duration::from_secs(60);
+
+// 客户端缓存，key 为 (集群名, namespace)
+type ClientKey = (String, String);
+
+struct CachedClient<T> {
+    cluster: NacosClusterConfig, // 创建时的集群配置，热加载后配置变化则重建
+    client: T,
+    last_used: Instant,
+}
+
+static CONFIG_SERVICES: Lazy<Mutex<HashMap<ClientKey, CachedClient<ConfigService>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+static NAMING_SERVICES: Lazy<Mutex<HashMap<ClientKey, CachedClient<NamingService>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+static EVICTOR: Once = Once::new();
+
+fn get_or_build<T: Clone>(
+    cache: &Mutex<HashMap<ClientKey, CachedClient<T>>>,
+    cluster: &NacosClusterConfig,
+    namespace: &str,
+    build: impl FnOnce(&NacosClusterConfig, &str) -> Result<T>,
+) -> Result<T> {
+    start_idle_evictor();
+    let key = (cluster.name.clone(), namespace.to_string());
+    let mut guard = cache.lock().unwrap();
+    if let Some(entry) = guard.get_mut(&key) {
+        if entry.cluster == *cluster {
+            entry.last_used = Instant::now();
+            return Ok(entry.client.clone());
+        }
+    }
+    let client = build(cluster, namespace)?;
+    guard.insert(
+        key,
+        CachedClient {
+            cluster: cluster.clone(),
+            client: client.clone(),
+            last_used: Instant::now(),
+        },
+    );
+    Ok(client)
+}
+
+/// 获取（必要时创建）指定集群与 namespace 的配置客户端
+pub fn get_config_service(cluster: &NacosClusterConfig, namespace: &str) -> Result<ConfigService> {
+    get_or_build(&CONFIG_SERVICES, cluster, namespace, build_config_service)
+}
+
+/// 获取（必要时创建）指定集群与 namespace 的命名客户端
+pub fn get_naming_service(cluster: &NacosClusterConfig, namespace: &str) -> Result<NamingService> {
+    get_or_build(&NAMING_SERVICES, cluster, namespace, build_naming_service)
+}
+
+fn evict_idle<T>(cache: &Mutex<HashMap<ClientKey, CachedClient<T>>>, idle: Duration) -> usize {
+    let mut guard = cache.lock().unwrap();
+    let before = guard.len();
+    guard.retain(|_, entry| entry.last_used.elapsed() < idle);
+    before - guard.len()
+}
+
+/// 后台定期清理长时间未使用的客户端，释放其与 Nacos 的长连接
+fn start_idle_evictor() {
+    EVICTOR.call_once(|| {
+        tokio::spawn(async {
+            let mut interval = tokio::time::interval(EVICTION_INTERVAL);
+            loop {
+                interval.tick().await;
+                let idle = Duration::from_secs(nacos_client_idle_secs());
+                let evicted = evict_idle(&CONFIG_SERVICES, idle) + evict_idle(&NAMING_SERVICES, idle);
+                if evicted > 0 {
+                    println!("[Nacos] 已清理 {} 个空闲客户端", evicted);
+                }
+            }
+        });
+    });
+}
@@ -1,14 +1,22 @@
 use crate::utils::config::{AppConfig, ElasticsearchFieldsConfig};
+use crate::utils::config_snapshot::{
+    config_snapshot_dir, load_snapshot, save_snapshot, set_config_error, set_config_source,
+    ConfigSource, ES_FIELDS_SNAPSHOT, MAIN_CONFIG_SNAPSHOT,
+};
+use crate::utils::file_watcher::watch_offline_config_files;
 use anyhow::Result;
-use nacos_sdk::api::config::{ConfigChangeListener, ConfigResponse, ConfigServiceBuilder};
-use nacos_sdk::api::props::ClientProps;
-use nacos_sdk::api::naming::NamingServiceBuilder;
+use crate::utils::nacos_client::{
+    build_config_service, default_nacos_cluster, get_config_service, get_naming_service,
+    resolve_nacos_cluster,
+};
+use nacos_sdk::api::config::{ConfigChangeListener, ConfigResponse};
 use once_cell::sync::Lazy;
 use serde_yaml;
 use serde_json;
 use std::env;
 use std::sync::{Arc, RwLock};
 use std::fs;
+use std::time::Duration;
 
 pub fn nacos_server_addr() -> String {
     env::var("NACOS_SERVER_ADDR").unwrap_or_else(|_| "127.0.0.1:8848".to_string())
@@ -39,28 +47,139 @@ pub fn get_config_file() -> String {
     env::var("CONFIG_FILE").unwrap_or_else(|_| "config-demo.yml".to_string())
 }
 
+pub fn get_es_fields_file() -> String {
+    env::var("ES_FIELDS_FILE").unwrap_or_else(|_| "elasticsearch-fields.yml".to_string())
+}
+
 pub fn read_config_from_file() -> Result<String> {
     let content = fs::read_to_string(get_config_file())?;
     Ok(content)
 }
 
+pub fn read_es_fields_config_from_file() -> Result<String> {
+    let content = fs::read_to_string(get_es_fields_file())?;
+    Ok(content)
+}
+
 pub async fn init_nacos_config() -> Result<()> {
     if is_offline_mode() {
-        println!("[Config] Running in offline mode, reading from config-demo.yml");
+        println!(
+            "[Config] Running in offline mode, reading from {} and {}",
+            get_config_file(),
+            get_es_fields_file()
+        );
         let content = read_config_from_file()?;
         reload_config_from_str(&content)?;
 
-        // 读取 elasticsearch-fields.yml
-        let es_fields_content = fs::read_to_string("elasticsearch-fields.yml")?;
+        // 读取 ES 字段配置文件
+        let es_fields_content = read_es_fields_config_from_file()?;
         reload_es_fields_config_from_str(&es_fields_content)?;
+
+        // 监听本地文件变更，实现与 Nacos 一致的热加载
+        watch_offline_config_files()?;
+        set_config_source(ConfigSource::File);
         return Ok(());
     }
 
-    let props = ClientProps::new()
-        .server_addr(&nacos_server_addr())
-        .namespace(&nacos_namespace())
-        .app_name("mcp-server");
-    let config_service = ConfigServiceBuilder::new(props).build()?;
+    // 启动时带退避重试连接 Nacos
+    let max_retries = nacos_connect_retries();
+    let mut attempt = 0;
+    let last_error = loop {
+        match load_config_from_nacos().await {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                eprintln!("[Nacos] 第 {} 次加载配置失败: {e}", attempt + 1);
+                set_config_error(&e.to_string());
+                if attempt >= max_retries {
+                    break e;
+                }
+                tokio::time::sleep(nacos_retry_delay(attempt)).await;
+                attempt += 1;
+            }
+        }
+    };
+
+    // 重试耗尽，回退到本地快照，后台继续尝试恢复实时配置
+    eprintln!("[Nacos] 无法连接 Nacos，尝试使用本地配置快照: {last_error}");
+    load_config_from_snapshot()
+        .map_err(|e| anyhow::anyhow!("Nacos 不可用且本地快照加载失败: {e}; Nacos 错误: {last_error}"))?;
+    set_config_source(ConfigSource::Cached);
+    tokio::spawn(reconnect_nacos_in_background());
+    Ok(())
+}
+
+pub fn nacos_connect_retries() -> u32 {
+    env::var("NACOS_CONNECT_RETRIES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(5)
+}
+
+pub fn nacos_retry_base_ms() -> u64 {
+    env::var("NACOS_RETRY_BASE_MS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(500)
+}
+
+const NACOS_RETRY_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// 指数退避：base * 2^attempt，最长 60 秒
+fn nacos_retry_delay(attempt: u32) -> Duration {
+    let delay = Duration::from_millis(nacos_retry_base_ms().saturating_mul(1u64 << attempt.min(16)));
+    delay.min(NACOS_RETRY_MAX_DELAY)
+}
+
+async fn reconnect_nacos_in_background() {
+    let mut attempt = 0;
+    loop {
+        tokio::time::sleep(nacos_retry_delay(attempt)).await;
+        match load_config_from_nacos().await {
+            Ok(()) => {
+                println!("[Nacos] 已恢复连接，切换为实时配置");
+                return;
+            }
+            Err(e) => {
+                set_config_error(&e.to_string());
+                attempt = attempt.saturating_add(1);
+            }
+        }
+    }
+}
+
+/// 两份快照都读取并解析成功后才应用，避免只加载一半
+fn load_config_from_snapshot() -> Result<()> {
+    let content = load_snapshot(MAIN_CONFIG_SNAPSHOT)?;
+    let es_fields_content = load_snapshot(ES_FIELDS_SNAPSHOT)?;
+    serde_yaml::from_str::<AppConfig>(&content)
+        .map_err(|e| anyhow::anyhow!("主配置快照解析失败: {e}"))?;
+    serde_yaml::from_str::<ElasticsearchFieldsConfig>(&es_fields_content)
+        .map_err(|e| anyhow::anyhow!("ES字段配置快照解析失败: {e}"))?;
+    reload_config_from_str(&content)?;
+    reload_es_fields_config_from_str(&es_fields_content)?;
+    println!("[Nacos] 已从本地快照 {} 加载配置", config_snapshot_dir());
+    Ok(())
+}
+
+/// 加载成功的 Nacos 配置写入本地快照，失败只记录日志不影响热加载
+fn apply_nacos_main_config(content: &str) -> Result<()> {
+    reload_config_from_str(content)?;
+    if let Err(e) = save_snapshot(MAIN_CONFIG_SNAPSHOT, content) {
+        eprintln!("[Nacos] 主配置快照写入失败: {e}");
+    }
+    Ok(())
+}
+
+fn apply_nacos_es_fields_config(content: &str) -> Result<()> {
+    reload_es_fields_config_from_str(content)?;
+    if let Err(e) = save_snapshot(ES_FIELDS_SNAPSHOT, content) {
+        eprintln!("[Nacos] ES字段配置快照写入失败: {e}");
+    }
+    Ok(())
+}
+
+async fn load_config_from_nacos() -> Result<()> {
+    let config_service = build_config_service(&default_nacos_cluster(), &nacos_namespace())?;
 
     // 读取主配置
     let data_id = nacos_data_id();
@@ -68,14 +187,14 @@ pub async fn init_nacos_config() -> Result<()> {
     let config_resp = config_service
         .get_config(data_id.clone(), group.clone())
         .await?;
-    reload_config_from_str(config_resp.content())?;
+    apply_nacos_main_config(config_resp.content())?;
 
     // 读取 elasticsearch-fields.yml 配置
     let es_fields_data_id = elasticsearch_fields_data_id();
     let es_fields_resp = config_service
         .get_config(es_fields_data_id.clone(), group.clone())
         .await?;
-    reload_es_fields_config_from_str(es_fields_resp.content())?;
+    apply_nacos_es_fields_config(es_fields_resp.content())?;
 
     // 监听配置变更
     struct Listener;
@@ -83,12 +202,16 @@ pub async fn init_nacos_config() -> Result<()> {
         fn notify(&self, config_resp: ConfigResponse) {
             let content = config_resp.content();
             if *config_resp.data_id() == nacos_data_id() {
-                if let Err(e) = reload_config_from_str(content) {
+                if let Err(e) = apply_nacos_main_config(content) {
                     eprintln!("[Nacos] 主配置热加载失败: {e}");
+                } else {
+                    set_config_source(ConfigSource::Live);
                 }
             } else if *config_resp.data_id() == elasticsearch_fields_data_id() {
-                if let Err(e) = reload_es_fields_config_from_str(content) {
+                if let Err(e) = apply_nacos_es_fields_config(content) {
                     eprintln!("[Nacos] ES字段配置热加载失败: {e}");
+                } else {
+                    set_config_source(ConfigSource::Live);
                 }
             }
         }
@@ -104,6 +227,7 @@ pub async fn init_nacos_config() -> Result<()> {
         .add_listener(es_fields_data_id, group, Arc::new(Listener))
         .await?;
 
+    set_config_source(ConfigSource::Live);
     Ok(())
 }
 
@@ -132,28 +256,19 @@ pub fn get_config_inner() -> Result<AppConfig> {
 }
 
 /// 通过namespace和data_id获取nacos配置内容
-pub async fn get_nacos_config_by(namespace: &str, data_id: &str, group: &str) -> Result<String> {
-    let props = ClientProps::new()
-        .server_addr(&nacos_server_addr())
-        .namespace(namespace)
-        .app_name("mcp-server");
-    let config_service = ConfigServiceBuilder::new(props).build()?;
+pub async fn get_nacos_config_by(cluster: Option<&str>, namespace: &str, data_id: &str, group: &str) -> Result<String> {
+    let cluster = resolve_nacos_cluster(cluster)?;
+    let config_service = get_config_service(&cluster, namespace)?;
     let config_resp = config_service.get_config(data_id.to_string(), group.to_string()).await?;
-    let content = config_resp.content().to_string();
-    // 显式drop配置服务
-    drop(config_service);
-    Ok(content)
+    Ok(config_resp.content().to_string())
 }
 
 /// 通过namespace和service_name获取服务实例信息
 type ServiceInfo = serde_json::Value;
 
-pub async fn get_nacos_service_info_by(namespace: &str, service_name: &str, group: Option<String>) -> Result<ServiceInfo> {
-    let props = ClientProps::new()
-        .server_addr(&nacos_server_addr())
-        .namespace(namespace)
-        .app_name("mcp-server");
-    let naming_service = NamingServiceBuilder::new(props).build()?;
+pub async fn get_nacos_service_info_by(cluster: Option<&str>, namespace: &str, service_name: &str, group: Option<String>) -> Result<ServiceInfo> {
+    let cluster = resolve_nacos_cluster(cluster)?;
+    let naming_service = get_naming_service(&cluster, namespace)?;
     // 获取所有实例（可根据需要调整参数）
     let instances = naming_service.get_all_instances(
         service_name.to_string(),
@@ -163,8 +278,6 @@ pub async fn get_nacos_service_info_by(namespace: &str, service_name: &str, grou
     ).await?;
     // 转为json
     let json = serde_json::to_value(&instances)?;
-    // 显式drop命名服务
-    drop(naming_service);
     Ok(json)
 }
 
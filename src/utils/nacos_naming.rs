@@ -0,0 +1,102 @@
+// Nacos 服务发现查询：服务列表、实例健康统计
+use anyhow::Result;
+use nacos_sdk::api::naming::{NamingService, ServiceInstance};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// 并发查询实例时的最大并发数，避免瞬间压垮 Nacos
+const MAX_CONCURRENT_QUERIES: usize = 16;
+const SERVICE_LIST_PAGE_SIZE: i32 = 500;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceHealthSummary {
+    pub service_name: String,
+    pub instance_count: usize,
+    pub healthy_count: usize,   // 已启用且健康
+    pub unhealthy_count: usize, // 已启用但不健康
+    pub disabled_count: usize,  // 已下线（enabled=false），三者之和等于 instance_count
+    pub clusters: Vec<String>,
+}
+
+impl ServiceHealthSummary {
+    pub fn from_instances(service_name: &str, instances: &[ServiceInstance]) -> Self {
+        let healthy_count = instances.iter().filter(|i| i.enabled && i.healthy).count();
+        let unhealthy_count = instances.iter().filter(|i| i.enabled && !i.healthy).count();
+        let disabled_count = instances.iter().filter(|i| !i.enabled).count();
+        let clusters: BTreeSet<String> = instances.iter().map(instance_cluster).collect();
+        Self {
+            service_name: service_name.to_string(),
+            instance_count: instances.len(),
+            healthy_count,
+            unhealthy_count,
+            disabled_count,
+            clusters: clusters.into_iter().collect(),
+        }
+    }
+}
+
+pub fn instance_cluster(instance: &ServiceInstance) -> String {
+    instance
+        .cluster_name
+        .clone()
+        .unwrap_or_else(|| "DEFAULT".to_string())
+}
+
+/// 按实例所属集群分组
+pub fn group_instances_by_cluster(instances: Vec<ServiceInstance>) -> BTreeMap<String, Vec<ServiceInstance>> {
+    let mut grouped: BTreeMap<String, Vec<ServiceInstance>> = BTreeMap::new();
+    for instance in instances {
+        grouped.entry(instance_cluster(&instance)).or_default().push(instance);
+    }
+    grouped
+}
+
+/// 翻页获取分组下的全部服务名
+pub async fn list_all_service_names(naming: &NamingService, group: Option<String>) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    let mut page_no = 1;
+    loop {
+        let (page, total) = naming
+            .get_service_list(page_no, SERVICE_LIST_PAGE_SIZE, group.clone())
+            .await?;
+        let fetched = page.len();
+        names.extend(page);
+        if fetched == 0 || names.len() >= total.max(0) as usize {
+            break;
+        }
+        page_no += 1;
+    }
+    Ok(names)
+}
+
+/// 并发查询每个服务的实例并统计健康情况，结果顺序与传入的服务名一致
+pub async fn summarize_services(
+    naming: &NamingService,
+    group: Option<String>,
+    service_names: Vec<String>,
+) -> Result<Vec<ServiceHealthSummary>> {
+    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_QUERIES));
+    let mut tasks = JoinSet::new();
+    for (index, service_name) in service_names.into_iter().enumerate() {
+        let naming = naming.clone();
+        let group = group.clone();
+        let semaphore = semaphore.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await?;
+            let instances = naming
+                .get_all_instances(service_name.clone(), group, Vec::new(), false)
+                .await?;
+            Ok::<_, anyhow::Error>((index, ServiceHealthSummary::from_instances(&service_name, &instances)))
+        });
+    }
+
+    let mut summaries = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        summaries.push(joined??);
+    }
+    summaries.sort_by_key(|(index, _)| *index);
+    Ok(summaries.into_iter().map(|(_, summary)| summary).collect())
+}
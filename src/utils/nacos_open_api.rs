@@ -0,0 +1,258 @@
+// Nacos Open API 封装：SDK 未提供的命名空间、配置检索、历史版本与服务订阅者查询
+use crate::utils::config::NacosClusterConfig;
+use crate::utils::nacos_client::{nacos_auth_mode, NacosAuthMode};
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const NACOS_CONTEXT_PATH: &str = "/nacos";
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+static HTTP_CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(HTTP_TIMEOUT)
+        .build()
+        .expect("failed to build Nacos http client")
+});
+
+struct CachedToken {
+    cluster: NacosClusterConfig,
+    token: String,
+    expires_at: Instant,
+}
+
+// 登录 token 缓存，key 为集群名
+static TOKENS: Lazy<Mutex<HashMap<String, CachedToken>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// "public" 命名空间在 Open API 中对应空的 tenant
+pub fn namespace_to_tenant(namespace: &str) -> &str {
+    if namespace == "public" {
+        ""
+    } else {
+        namespace
+    }
+}
+
+/// Open API 只支持用户名密码或无鉴权；AK/SK（如阿里云 MSE）集群只能使用 SDK 类工具
+pub fn open_api_supported(cluster: &NacosClusterConfig) -> bool {
+    nacos_auth_mode(cluster) != NacosAuthMode::AccessKey
+}
+
+pub struct NacosOpenApi {
+    cluster: NacosClusterConfig,
+}
+
+impl NacosOpenApi {
+    pub fn new(cluster: NacosClusterConfig) -> Result<Self> {
+        if !open_api_supported(&cluster) {
+            return Err(anyhow::anyhow!(
+                "Nacos cluster '{}' uses AK/SK auth (e.g. Aliyun MSE), the Nacos open API tools are unsupported for this cluster; use GetNacosConfigByClient or GetNacosServiceInfoByClient instead",
+                cluster.name
+            ));
+        }
+        Ok(Self { cluster })
+    }
+
+    /// token 被服务端拒绝（过期或服务端重启）时清除缓存，下次请求重新登录
+    fn invalidate_token(&self) {
+        TOKENS.lock().unwrap().remove(&self.cluster.name);
+    }
+
+    /// server_addr 中的每个地址转成 http 基础地址，逐个尝试实现故障转移
+    fn base_urls(&self) -> Vec<String> {
+        self.cluster
+            .server_addr
+            .split(',')
+            .map(|addr| addr.trim().trim_end_matches('/'))
+            .filter(|addr| !addr.is_empty())
+            .map(|addr| {
+                if addr.starts_with("http://") || addr.starts_with("https://") {
+                    addr.to_string()
+                } else {
+                    format!("http://{}", addr)
+                }
+            })
+            .collect()
+    }
+
+    async fn login(&self, base_url: &str) -> Result<Option<String>> {
+        let (Some(username), Some(password)) = (&self.cluster.username, &self.cluster.password) else {
+            return Ok(None);
+        };
+        {
+            let guard = TOKENS.lock().unwrap();
+            if let Some(cached) = guard.get(&self.cluster.name) {
+                if cached.cluster == self.cluster && cached.expires_at > Instant::now() {
+                    return Ok(Some(cached.token.clone()));
+                }
+            }
+        }
+
+        let response = HTTP_CLIENT
+            .post(format!("{}{}/v1/auth/login", base_url, NACOS_CONTEXT_PATH))
+            .form(&[("username", username.as_str()), ("password", password.as_str())])
+            .send()
+            .await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(anyhow::anyhow!("Nacos login failed ({}): {}", status, body));
+        }
+        let body: Value = serde_json::from_str(&body)?;
+        let token = body["accessToken"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Nacos login response has no accessToken"))?
+            .to_string();
+        // 提前 60 秒过期，避免边界时刻使用失效 token
+        let ttl = body["tokenTtl"].as_u64().unwrap_or(18000).saturating_sub(60);
+        TOKENS.lock().unwrap().insert(
+            self.cluster.name.clone(),
+            CachedToken {
+                cluster: self.cluster.clone(),
+                token: token.clone(),
+                expires_at: Instant::now() + Duration::from_secs(ttl),
+            },
+        );
+        Ok(Some(token))
+    }
+
+    async fn send_get(
+        &self,
+        base_url: &str,
+        path: &str,
+        query: &[(&str, String)],
+        token: Option<String>,
+    ) -> Result<reqwest::Response> {
+        let mut request = HTTP_CLIENT
+            .get(format!("{}{}{}", base_url, NACOS_CONTEXT_PATH, path))
+            .query(query);
+        if let Some(token) = token {
+            request = request.query(&[("accessToken", token)]);
+        }
+        Ok(request.send().await?)
+    }
+
+    /// GET 请求，依次尝试每个节点，仅在连接失败时切换节点；401/403 时重新登录并重试一次
+    pub async fn get(&self, path: &str, query: &[(&str, String)]) -> Result<Value> {
+        let mut last_error = None;
+        for base_url in self.base_urls() {
+            let token = match self.login(&base_url).await {
+                Ok(token) => token,
+                Err(e) => {
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+            let has_token = token.is_some();
+            let mut response = match self.send_get(&base_url, path, query, token).await {
+                Ok(response) => response,
+                Err(e) => {
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+            if has_token && matches!(response.status().as_u16(), 401 | 403) {
+                println!("[Nacos] 集群 {} 的 accessToken 被拒绝，重新登录后重试", self.cluster.name);
+                self.invalidate_token();
+                let token = self.login(&base_url).await?;
+                response = self.send_get(&base_url, path, query, token).await?;
+            }
+            let status = response.status();
+            let body = response.text().await?;
+            if !status.is_success() {
+                return Err(anyhow::anyhow!("Nacos open API {} failed ({}): {}", path, status, body));
+            }
+            return Ok(serde_json::from_str(&body).unwrap_or(Value::String(body)));
+        }
+        Err(last_error.unwrap_or_else(|| {
+            anyhow::anyhow!("Nacos cluster '{}' has no server address", self.cluster.name)
+        }))
+    }
+
+    pub async fn list_namespaces(&self) -> Result<Value> {
+        let body = self.get("/v1/console/namespaces", &[]).await?;
+        Ok(body.get("data").cloned().unwrap_or(body))
+    }
+
+    /// 模糊检索配置，data_id / group 支持 * 通配，keyword 匹配配置内容
+    pub async fn search_configs(
+        &self,
+        namespace: &str,
+        data_id: &str,
+        group: &str,
+        keyword: Option<&str>,
+        page_no: u32,
+        page_size: u32,
+    ) -> Result<Value> {
+        let mut query = vec![
+            ("search", "blur".to_string()),
+            ("dataId", data_id.to_string()),
+            ("group", group.to_string()),
+            ("tenant", namespace_to_tenant(namespace).to_string()),
+            ("pageNo", page_no.to_string()),
+            ("pageSize", page_size.to_string()),
+        ];
+        if let Some(keyword) = keyword.filter(|k| !k.is_empty()) {
+            query.push(("config_detail", keyword.to_string()));
+        }
+        self.get("/v1/cs/configs", &query).await
+    }
+
+    pub async fn config_history(
+        &self,
+        namespace: &str,
+        data_id: &str,
+        group: &str,
+        page_no: u32,
+        page_size: u32,
+    ) -> Result<Value> {
+        let query = [
+            ("search", "accurate".to_string()),
+            ("dataId", data_id.to_string()),
+            ("group", group.to_string()),
+            ("tenant", namespace_to_tenant(namespace).to_string()),
+            ("pageNo", page_no.to_string()),
+            ("pageSize", page_size.to_string()),
+        ];
+        self.get("/v1/cs/history", &query).await
+    }
+
+    /// 获取某个历史版本（nid 来自 config_history 的 id 字段）的完整内容
+    pub async fn config_revision(
+        &self,
+        namespace: &str,
+        data_id: &str,
+        group: &str,
+        nid: &str,
+    ) -> Result<Value> {
+        let query = [
+            ("nid", nid.to_string()),
+            ("dataId", data_id.to_string()),
+            ("group", group.to_string()),
+            ("tenant", namespace_to_tenant(namespace).to_string()),
+        ];
+        self.get("/v1/cs/history", &query).await
+    }
+
+    /// 查询订阅了某个服务的客户端列表
+    pub async fn service_subscribers(
+        &self,
+        namespace: &str,
+        service_name: &str,
+        group: &str,
+        page_no: u32,
+        page_size: u32,
+    ) -> Result<Value> {
+        let query = [
+            ("serviceName", service_name.to_string()),
+            ("groupName", group.to_string()),
+            ("namespaceId", namespace_to_tenant(namespace).to_string()),
+            ("pageNo", page_no.to_string()),
+            ("pageSize", page_size.to_string()),
+        ];
+        self.get("/v1/ns/service/subscribers", &query).await
+    }
+}
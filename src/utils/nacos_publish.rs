@@ -0,0 +1,381 @@
+// Nacos 配置发布：先预览 diff 并下发变更令牌，携带令牌再次调用才真正发布
+use crate::utils::config::NacosConfig;
+use crate::utils::date_util::now_datetime_string;
+use crate::utils::nacos_client::{get_config_service, resolve_nacos_cluster, DEFAULT_NACOS_CLUSTER};
+use crate::utils::nacos_config::get_config_inner;
+use anyhow::Result;
+use nacos_sdk::api::error::Error as NacosError;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use similar::TextDiff;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::env;
+use std::fs::OpenOptions;
+use std::hash::{BuildHasher, Hasher};
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// 变更令牌有效期
+const CHANGE_TOKEN_TTL: Duration = Duration::from_secs(600);
+
+pub fn nacos_publish_allow_prod() -> bool {
+    env::var("NACOS_PUBLISH_ALLOW_PROD").unwrap_or_else(|_| "false".to_string()) == "true"
+}
+
+pub fn nacos_publish_audit_log() -> String {
+    env::var("NACOS_PUBLISH_AUDIT_LOG").unwrap_or_else(|_| "nacos-publish-audit.log".to_string())
+}
+
+/// 一次待确认的发布
+#[derive(Debug, Clone, PartialEq)]
+struct PendingChange {
+    cluster: String,
+    namespace: String,
+    data_id: String,
+    group: String,
+    content: String,
+    content_type: Option<String>,
+    base_md5: Option<String>, // 预览时的内容 MD5，配置不存在时为 None
+}
+
+static PENDING_CHANGES: Lazy<Mutex<HashMap<String, (PendingChange, Instant)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PublishPreview {
+    pub change_token: String,
+    pub expires_in_secs: u64,
+    pub current_md5: Option<String>,
+    pub is_new_config: bool,
+    pub diff: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct AuditRecord<'a> {
+    time: String,
+    cluster: &'a str,
+    namespace: &'a str,
+    data_id: &'a str,
+    group: &'a str,
+    base_md5: Option<&'a str>,
+    change_token: &'a str,
+    result: &'a str,
+    diff: &'a str,
+}
+
+/// 生产环境标记，按完整单词匹配（避免 "product" 之类误判）
+const PROD_TOKENS: [&str; 3] = ["prod", "production", "prd"];
+
+fn has_prod_token(text: &str) -> bool {
+    text.contains("生产")
+        || text
+            .to_lowercase()
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .any(|token| PROD_TOKENS.contains(&token))
+}
+
+/// nacos 配置项所属集群，未填写时为默认集群（与 resolve_nacos_cluster 一致）
+fn entry_cluster(entry: &NacosConfig) -> &str {
+    entry
+        .cluster
+        .as_deref()
+        .filter(|c| !c.is_empty())
+        .unwrap_or(DEFAULT_NACOS_CLUSTER)
+}
+
+/// 按 (集群, namespace) 查找配置项，同一 namespace id 在不同集群中互不影响
+fn namespace_entries<'a>(
+    entries: &'a [NacosConfig],
+    cluster: &'a str,
+    namespace: &'a str,
+) -> impl Iterator<Item = &'a NacosConfig> {
+    entries
+        .iter()
+        .filter(move |n| entry_cluster(n) == cluster && n.namespace == namespace)
+}
+
+fn prod_in(entries: &[NacosConfig], cluster: &str, namespace: &str) -> bool {
+    has_prod_token(cluster)
+        || has_prod_token(namespace)
+        || namespace_entries(entries, cluster, namespace)
+            .any(|n| n.environment.as_deref().is_some_and(has_prod_token) || has_prod_token(&n.description))
+}
+
+fn writable_in(entries: &[NacosConfig], cluster: &str, namespace: &str) -> bool {
+    namespace_entries(entries, cluster, namespace)
+        .any(|n| n.environment.as_deref().is_some_and(|e| !e.trim().is_empty()))
+}
+
+/// 判断集群下的 namespace 是否为生产环境：集群名 / namespace 含有 prod 单词或“生产”，或对应配置项的 environment / 描述如此
+pub fn is_prod_namespace(cluster: &str, namespace: &str) -> bool {
+    let entries = get_config_inner().map(|config| config.nacos).unwrap_or_default();
+    prod_in(&entries, cluster, namespace)
+}
+
+/// 只有在 nacos 配置中为该集群显式声明了 environment 的 namespace 才允许发布，未登记的 namespace（如 UUID）一律拒绝
+pub fn is_writable_namespace(cluster: &str, namespace: &str) -> bool {
+    get_config_inner()
+        .map(|config| writable_in(&config.nacos, cluster, namespace))
+        .unwrap_or(false)
+}
+
+fn new_change_token() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(nanos);
+    format!("{:016x}", hasher.finish())
+}
+
+pub fn unified_diff(current: &str, proposed: &str) -> String {
+    TextDiff::from_lines(current, proposed)
+        .unified_diff()
+        .context_radius(3)
+        .header("current", "proposed")
+        .to_string()
+}
+
+/// 读取当前配置，不存在时返回 None
+async fn fetch_current(
+    cluster: Option<&str>,
+    namespace: &str,
+    data_id: &str,
+    group: &str,
+) -> Result<Option<(String, String)>> {
+    let config_service = get_config_service(&resolve_nacos_cluster(cluster)?, namespace)?;
+    match config_service.get_config(data_id.to_string(), group.to_string()).await {
+        Ok(resp) => Ok(Some((resp.content().to_string(), resp.md5().to_string()))),
+        Err(NacosError::ConfigNotFound(_)) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// cluster 为解析后的集群名，与创建客户端使用的集群一致
+fn check_prod_guard(cluster: &str, namespace: &str) -> Result<()> {
+    if !is_writable_namespace(cluster, namespace) {
+        return Err(anyhow::anyhow!(
+            "Namespace '{}' of cluster '{}' is not listed as writable: add it to the nacos section of the config with this cluster and an environment (e.g. test) to allow publishing",
+            namespace,
+            cluster
+        ));
+    }
+    if is_prod_namespace(cluster, namespace) && !nacos_publish_allow_prod() {
+        return Err(anyhow::anyhow!(
+            "Publishing to production namespace '{}' of cluster '{}' is disabled (set NACOS_PUBLISH_ALLOW_PROD=true to allow)",
+            namespace,
+            cluster
+        ));
+    }
+    Ok(())
+}
+
+/// 第一步：与当前内容做 diff，登记待发布变更并返回令牌
+pub async fn preview_publish(
+    cluster: Option<&str>,
+    namespace: &str,
+    data_id: &str,
+    group: &str,
+    content: &str,
+    content_type: Option<String>,
+) -> Result<PublishPreview> {
+    let resolved = resolve_nacos_cluster(cluster)?;
+    check_prod_guard(&resolved.name, namespace)?;
+    let current = fetch_current(cluster, namespace, data_id, group).await?;
+    let (current_content, current_md5) = match &current {
+        Some((content, md5)) => (content.as_str(), Some(md5.clone())),
+        None => ("", None),
+    };
+
+    let change_token = new_change_token();
+    let pending = PendingChange {
+        cluster: resolved.name,
+        namespace: namespace.to_string(),
+        data_id: data_id.to_string(),
+        group: group.to_string(),
+        content: content.to_string(),
+        content_type,
+        base_md5: current_md5.clone(),
+    };
+    {
+        let mut guard = PENDING_CHANGES.lock().unwrap();
+        guard.retain(|_, (_, created)| created.elapsed() < CHANGE_TOKEN_TTL);
+        guard.insert(change_token.clone(), (pending, Instant::now()));
+    }
+
+    Ok(PublishPreview {
+        change_token,
+        expires_in_secs: CHANGE_TOKEN_TTL.as_secs(),
+        current_md5,
+        is_new_config: current.is_none(),
+        diff: unified_diff(current_content, content),
+    })
+}
+
+/// 第二步：校验令牌与参数一致、内容未被并发修改后，以 CAS 方式发布并写审计记录
+pub async fn apply_publish(
+    change_token: &str,
+    cluster: Option<&str>,
+    namespace: &str,
+    data_id: &str,
+    group: &str,
+    content: &str,
+    content_type: Option<String>,
+) -> Result<String> {
+    let resolved = resolve_nacos_cluster(cluster)?;
+    check_prod_guard(&resolved.name, namespace)?;
+    let (pending, created) = PENDING_CHANGES
+        .lock()
+        .unwrap()
+        .remove(change_token)
+        .ok_or_else(|| anyhow::anyhow!("Unknown or already used change token '{}', run a dry run first", change_token))?;
+    if created.elapsed() >= CHANGE_TOKEN_TTL {
+        return Err(anyhow::anyhow!("Change token '{}' has expired, run a dry run again", change_token));
+    }
+    let requested = PendingChange {
+        cluster: resolved.name.clone(),
+        namespace: namespace.to_string(),
+        data_id: data_id.to_string(),
+        group: group.to_string(),
+        content: content.to_string(),
+        content_type: content_type.clone(),
+        base_md5: pending.base_md5.clone(),
+    };
+    if requested != pending {
+        return Err(anyhow::anyhow!(
+            "Change token '{}' was issued for a different config or content, run a dry run again",
+            change_token
+        ));
+    }
+
+    let current = fetch_current(cluster, namespace, data_id, group).await?;
+    let current_content = current.as_ref().map(|(c, _)| c.clone()).unwrap_or_default();
+    let current_md5 = current.map(|(_, md5)| md5);
+    if current_md5 != pending.base_md5 {
+        return Err(anyhow::anyhow!(
+            "Config {} ({}) was modified since the dry run (md5 {:?} -> {:?}), run a dry run again",
+            data_id,
+            group,
+            pending.base_md5,
+            current_md5
+        ));
+    }
+
+    let config_service = get_config_service(&resolved, namespace)?;
+    let published = match &pending.base_md5 {
+        Some(base_md5) => {
+            config_service
+                .publish_config_cas(
+                    data_id.to_string(),
+                    group.to_string(),
+                    content.to_string(),
+                    content_type,
+                    base_md5.clone(),
+                )
+                .await
+        }
+        // 新建配置无法 CAS（空 MD5 会被服务端当作普通发布），发布前再确认一次配置仍不存在
+        None => match config_service.get_config(data_id.to_string(), group.to_string()).await {
+            Err(NacosError::ConfigNotFound(_)) => {
+                config_service
+                    .publish_config(data_id.to_string(), group.to_string(), content.to_string(), content_type)
+                    .await
+            }
+            Ok(_) => Ok(false),
+            Err(e) => Err(e),
+        },
+    };
+
+    let diff = unified_diff(&current_content, content);
+    let result = match &published {
+        Ok(true) => "published".to_string(),
+        Ok(false) => "rejected".to_string(),
+        Err(e) => format!("error: {}", e),
+    };
+    write_audit_record(&AuditRecord {
+        time: now_datetime_string(),
+        cluster: &resolved.name,
+        namespace,
+        data_id,
+        group,
+        base_md5: pending.base_md5.as_deref(),
+        change_token,
+        result: &result,
+        diff: &diff,
+    });
+
+    match published? {
+        true => Ok(format!("Published {} ({}) to namespace '{}'", data_id, group, namespace)),
+        false => Err(anyhow::anyhow!(
+            "Nacos rejected the publish of {} ({}), the config may have been modified concurrently",
+            data_id,
+            group
+        )),
+    }
+}
+
+/// 审计记录以 JSON Lines 追加写入，写入失败只记录日志
+fn write_audit_record(record: &AuditRecord) {
+    let result = serde_json::to_string(record)
+        .map_err(anyhow::Error::from)
+        .and_then(|line| {
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(nacos_publish_audit_log())?;
+            writeln!(file, "{}", line)?;
+            Ok(())
+        });
+    if let Err(e) = result {
+        eprintln!("[Nacos] 发布审计记录写入失败: {e}");
+    }
+    println!(
+        "[Nacos] 配置发布 {} {}/{} ({}): {}",
+        record.cluster, record.namespace, record.data_id, record.group, record.result
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prod_token_matches_whole_words_only() {
+        assert!(has_prod_token("prod"));
+        assert!(has_prod_token("mse-PROD-01"));
+        assert!(has_prod_token("生产环境"));
+        assert!(!has_prod_token("product-center"));
+        assert!(!has_prod_token("reproduce"));
+    }
+
+    fn entry(cluster: Option<&str>, namespace: &str, environment: Option<&str>) -> NacosConfig {
+        NacosConfig {
+            description: String::new(),
+            namespace: namespace.to_string(),
+            cluster: cluster.map(str::to_string),
+            environment: environment.map(str::to_string),
+            data_ids: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn guard_matches_cluster_and_namespace_pair() {
+        let entries = [
+            entry(Some("test"), "public", Some("test")),
+            entry(Some("online"), "public", Some("prod")),
+            entry(None, "dev", Some("dev")),
+        ];
+        assert!(writable_in(&entries, "test", "public"));
+        assert!(!prod_in(&entries, "test", "public"));
+        assert!(writable_in(&entries, "online", "public"));
+        assert!(prod_in(&entries, "online", "public"));
+        // 未登记到该集群的同名 namespace 不可写
+        assert!(!writable_in(&entries, "staging", "public"));
+        assert!(!writable_in(&entries, "test", "dev"));
+        assert!(writable_in(&entries, DEFAULT_NACOS_CLUSTER, "dev"));
+        assert!(prod_in(&entries, "prod-cluster", "dev"));
+    }
+}
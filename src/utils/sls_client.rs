@@ -0,0 +1,449 @@
+// 阿里云日志服务（SLS）原生 HTTP 客户端，替代外部 aliyunlog / aliyun CLI
+// API 文档 https://help.aliyun.com/zh/sls/developer-reference/api-sls-2020-12-30-getlogs
+use anyhow::Result;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use serde_json::Value;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::env;
+use std::net::IpAddr;
+use std::time::Duration;
+
+const API_VERSION: &str = "0.6.0";
+const HTTP_TIMEOUT: Duration = Duration::from_secs(60);
+
+pub fn aliyun_access_key_id() -> String {
+    env::var("ALIYUN_ACCESS_KEY_ID").unwrap_or_default()
+}
+pub fn aliyun_access_key_secret() -> String {
+    env::var("ALIYUN_ACCESS_KEY_SECRET").unwrap_or_default()
+}
+pub fn aliyun_region_id() -> String {
+    env::var("ALIYUN_REGION_ID").unwrap_or_else(|_| "cn-beijing".to_string())
+}
+/// SLS 服务地址，默认 {region}.log.aliyuncs.com；本地调试可指向 mock 服务，如 http://127.0.0.1:8080
+pub fn sls_endpoint() -> String {
+    env::var("SLS_ENDPOINT").unwrap_or_else(|_| format!("{}.log.aliyuncs.com", aliyun_region_id()))
+}
+pub fn sls_signature_version() -> String {
+    env::var("SLS_SIGNATURE_VERSION").unwrap_or_else(|_| "v4".to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureVersion {
+    V1,
+    V4,
+}
+
+/// GetLogs 请求参数
+#[derive(Debug, Clone)]
+pub struct GetLogsRequest {
+    pub from: i64,
+    pub to: i64,
+    pub query: String,
+    pub topic: Option<String>,
+    pub line: u32,
+    pub offset: u32,
+    pub reverse: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GetLogsResponse {
+    pub progress: String, // Complete 表示结果完整，Incomplete 需重试
+    pub count: usize,
+    pub logs: Vec<Value>,
+}
+
+impl GetLogsResponse {
+    pub fn is_complete(&self) -> bool {
+        self.progress == "Complete"
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GetHistogramsResponse {
+    pub progress: String,
+    pub count: u64,
+    pub histograms: Value,
+}
+
+pub struct SlsClient {
+    http: reqwest::Client,
+    endpoint: String,
+    access_key_id: String,
+    access_key_secret: String,
+    region: String,
+    signature_version: SignatureVersion,
+}
+
+type HmacSha1 = Hmac<Sha1>;
+type HmacSha256 = Hmac<Sha256>;
+
+fn hmac_sha1(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha1::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// v4 签名要求的 URI 编码（RFC 3986 非保留字符不编码）
+fn percent_encode_v4(value: &str) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// 规范化查询串：签名与实际请求 URL 共用，保证空格等字符编码一致（%20 而非 +）
+fn canonical_query(params: &BTreeMap<String, String>) -> String {
+    params
+        .iter()
+        .map(|(k, v)| format!("{}={}", percent_encode_v4(k), percent_encode_v4(v)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// logstore 作为路径段需要编码
+fn logstore_resource(logstore: &str) -> String {
+    format!("/logstores/{}", percent_encode_v4(logstore))
+}
+
+impl SlsClient {
+    /// 使用环境变量中的 AK/SK 与 region 创建客户端，endpoint 为空时使用 SLS_ENDPOINT
+    pub fn from_env(endpoint: Option<&str>) -> Result<Self> {
+        let access_key_id = aliyun_access_key_id();
+        let access_key_secret = aliyun_access_key_secret();
+        if access_key_id.is_empty() || access_key_secret.is_empty() {
+            return Err(anyhow::anyhow!(
+                "ALIYUN_ACCESS_KEY_ID and ALIYUN_ACCESS_KEY_SECRET must be set to query SLS"
+            ));
+        }
+        let signature_version = match sls_signature_version().to_lowercase().as_str() {
+            "v1" => SignatureVersion::V1,
+            "v4" => SignatureVersion::V4,
+            other => return Err(anyhow::anyhow!("Unsupported SLS_SIGNATURE_VERSION: {}", other)),
+        };
+        let http = reqwest::Client::builder().timeout(HTTP_TIMEOUT).build()?;
+        Ok(Self {
+            http,
+            endpoint: endpoint.map(str::to_string).unwrap_or_else(sls_endpoint),
+            access_key_id,
+            access_key_secret,
+            region: aliyun_region_id(),
+            signature_version,
+        })
+    }
+
+    /// 解析出 (scheme, host)，project 作为子域名；endpoint 为 IP 或 localhost 时（mock 服务）不拼接 project
+    fn project_base(&self, project: &str) -> (String, String) {
+        let (scheme, host) = match self.endpoint.split_once("://") {
+            Some((scheme, host)) => (scheme.to_string(), host.trim_end_matches('/').to_string()),
+            None => ("https".to_string(), self.endpoint.trim_end_matches('/').to_string()),
+        };
+        let hostname = host.split(':').next().unwrap_or_default();
+        if hostname == "localhost" || hostname.parse::<IpAddr>().is_ok() {
+            (scheme, host)
+        } else {
+            (scheme, format!("{}.{}", project, host))
+        }
+    }
+
+    async fn get(&self, project: &str, resource: &str, params: &BTreeMap<String, String>) -> Result<(reqwest::header::HeaderMap, String)> {
+        let (scheme, host) = self.project_base(project);
+        let mut headers: BTreeMap<String, String> = BTreeMap::new();
+        headers.insert("x-log-apiversion".to_string(), API_VERSION.to_string());
+        headers.insert("x-log-bodyrawsize".to_string(), "0".to_string());
+        headers.insert("host".to_string(), host.clone());
+
+        let authorization = match self.signature_version {
+            SignatureVersion::V1 => {
+                let date = Utc::now().format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+                headers.insert("date".to_string(), date.clone());
+                headers.insert("x-log-signaturemethod".to_string(), "hmac-sha1".to_string());
+                self.sign_v1(resource, params, &headers, &date)
+            }
+            SignatureVersion::V4 => {
+                let now = Utc::now();
+                let datetime = now.format("%Y%m%dT%H%M%SZ").to_string();
+                let date = now.format("%Y%m%d").to_string();
+                let payload_hash = hex::encode(Sha256::digest(b""));
+                headers.insert("x-log-date".to_string(), datetime.clone());
+                headers.insert("x-log-content-sha256".to_string(), payload_hash.clone());
+                self.sign_v4(resource, params, &headers, &datetime, &date, &payload_hash)
+            }
+        };
+
+        let mut url = format!("{}://{}{}", scheme, host, resource);
+        if !params.is_empty() {
+            url.push('?');
+            url.push_str(&canonical_query(params));
+        }
+        let mut request = self
+            .http
+            .get(url)
+            .header(reqwest::header::AUTHORIZATION, authorization);
+        for (key, value) in &headers {
+            if key != "host" {
+                request = request.header(key.as_str(), value.as_str());
+            }
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        let response_headers = response.headers().clone();
+        let body = response.text().await?;
+        if !status.is_success() {
+            let request_id = response_headers
+                .get("x-log-requestid")
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("");
+            let error: Value = serde_json::from_str(&body).unwrap_or(Value::Null);
+            return Err(anyhow::anyhow!(
+                "SLS request failed ({}): {} {} [RequestId: {}]",
+                status,
+                error["errorCode"].as_str().unwrap_or(""),
+                error["errorMessage"].as_str().unwrap_or(&body),
+                request_id
+            ));
+        }
+        Ok((response_headers, body))
+    }
+
+    /// 签名 v1：Authorization: LOG AccessKeyId:base64(hmac-sha1(secret, StringToSign))
+    fn sign_v1(
+        &self,
+        resource: &str,
+        params: &BTreeMap<String, String>,
+        headers: &BTreeMap<String, String>,
+        date: &str,
+    ) -> String {
+        let canonical_headers: Vec<String> = headers
+            .iter()
+            .filter(|(k, _)| k.starts_with("x-log-") || k.starts_with("x-acs-"))
+            .map(|(k, v)| format!("{}:{}", k, v))
+            .collect();
+        let mut canonical_resource = resource.to_string();
+        if !params.is_empty() {
+            let query: Vec<String> = params.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+            canonical_resource.push('?');
+            canonical_resource.push_str(&query.join("&"));
+        }
+        // GET 请求没有 body，Content-MD5 与 Content-Type 为空
+        let string_to_sign = format!(
+            "GET\n\n\n{}\n{}\n{}",
+            date,
+            canonical_headers.join("\n"),
+            canonical_resource
+        );
+        let signature = BASE64.encode(hmac_sha1(self.access_key_secret.as_bytes(), &string_to_sign));
+        format!("LOG {}:{}", self.access_key_id, signature)
+    }
+
+    /// 签名 v4：SLS4-HMAC-SHA256，签名密钥由 date / region / sls / aliyun_v4_request 逐级派生
+    fn sign_v4(
+        &self,
+        resource: &str,
+        params: &BTreeMap<String, String>,
+        headers: &BTreeMap<String, String>,
+        datetime: &str,
+        date: &str,
+        payload_hash: &str,
+    ) -> String {
+        let signed_headers: Vec<&str> = headers.keys().map(String::as_str).collect();
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(k, v)| format!("{}:{}\n", k, v.trim()))
+            .collect();
+        let canonical_request = format!(
+            "GET\n{}\n{}\n{}\n{}\n{}",
+            resource,
+            canonical_query(params),
+            canonical_headers,
+            signed_headers.join(";"),
+            payload_hash
+        );
+        let scope = format!("{}/{}/sls/aliyun_v4_request", date, self.region);
+        let string_to_sign = format!(
+            "SLS4-HMAC-SHA256\n{}\n{}\n{}",
+            datetime,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+        let k_date = hmac_sha256(format!("aliyun_v4{}", self.access_key_secret).as_bytes(), date);
+        let k_region = hmac_sha256(&k_date, &self.region);
+        let k_service = hmac_sha256(&k_region, "sls");
+        let k_signing = hmac_sha256(&k_service, "aliyun_v4_request");
+        let signature = hex::encode(hmac_sha256(&k_signing, &string_to_sign));
+        format!(
+            "SLS4-HMAC-SHA256 Credential={}/{},Signature={}",
+            self.access_key_id, scope, signature
+        )
+    }
+
+    /// 查询日志，from / to 为 Unix 秒
+    pub async fn get_logs(&self, project: &str, logstore: &str, request: &GetLogsRequest) -> Result<GetLogsResponse> {
+        let mut params = BTreeMap::new();
+        params.insert("type".to_string(), "log".to_string());
+        params.insert("from".to_string(), request.from.to_string());
+        params.insert("to".to_string(), request.to.to_string());
+        params.insert("query".to_string(), request.query.clone());
+        params.insert("line".to_string(), request.line.to_string());
+        params.insert("offset".to_string(), request.offset.to_string());
+        params.insert("reverse".to_string(), request.reverse.to_string());
+        if let Some(topic) = &request.topic {
+            params.insert("topic".to_string(), topic.clone());
+        }
+        let (headers, body) = self
+            .get(project, &logstore_resource(logstore), &params)
+            .await?;
+        let logs: Vec<Value> = serde_json::from_str(&body)?;
+        Ok(GetLogsResponse {
+            progress: header_str(&headers, "x-log-progress"),
+            count: logs.len(),
+            logs,
+        })
+    }
+
+    /// 查询日志数量分布
+    pub async fn get_histograms(
+        &self,
+        project: &str,
+        logstore: &str,
+        from: i64,
+        to: i64,
+        query: &str,
+        topic: Option<&str>,
+    ) -> Result<GetHistogramsResponse> {
+        let mut params = BTreeMap::new();
+        params.insert("type".to_string(), "histogram".to_string());
+        params.insert("from".to_string(), from.to_string());
+        params.insert("to".to_string(), to.to_string());
+        params.insert("query".to_string(), query.to_string());
+        if let Some(topic) = topic {
+            params.insert("topic".to_string(), topic.to_string());
+        }
+        let (headers, body) = self
+            .get(project, &logstore_resource(logstore), &params)
+            .await?;
+        Ok(GetHistogramsResponse {
+            progress: header_str(&headers, "x-log-progress"),
+            count: header_str(&headers, "x-log-count").parse().unwrap_or_default(),
+            histograms: serde_json::from_str(&body)?,
+        })
+    }
+
+    pub async fn list_logstores(&self, project: &str, name_filter: Option<&str>, offset: u32, size: u32) -> Result<Value> {
+        let mut params = BTreeMap::new();
+        params.insert("offset".to_string(), offset.to_string());
+        params.insert("size".to_string(), size.to_string());
+        if let Some(name) = name_filter {
+            params.insert("logstoreName".to_string(), name.to_string());
+        }
+        let (_, body) = self.get(project, "/logstores", &params).await?;
+        Ok(serde_json::from_str(&body)?)
+    }
+}
+
+fn header_str(headers: &reqwest::header::HeaderMap, name: &str) -> String {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOST: &str = "demo.cn-hangzhou.log.aliyuncs.com";
+
+    fn client(signature_version: SignatureVersion) -> SlsClient {
+        SlsClient {
+            http: reqwest::Client::new(),
+            endpoint: "cn-hangzhou.log.aliyuncs.com".to_string(),
+            access_key_id: "testid".to_string(),
+            access_key_secret: "testsecret".to_string(),
+            region: "cn-hangzhou".to_string(),
+            signature_version,
+        }
+    }
+
+    fn params() -> BTreeMap<String, String> {
+        [
+            ("type", "log"),
+            ("from", "1700000000"),
+            ("to", "1700000900"),
+            ("query", "* | select count(1)"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+    }
+
+    fn headers(extra: &[(&str, &str)]) -> BTreeMap<String, String> {
+        [
+            ("x-log-apiversion", API_VERSION),
+            ("x-log-bodyrawsize", "0"),
+            ("host", HOST),
+        ]
+        .into_iter()
+        .chain(extra.iter().copied())
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+    }
+
+    #[test]
+    fn canonical_query_uses_percent_twenty() {
+        assert_eq!(
+            canonical_query(&params()),
+            "from=1700000000&query=%2A%20%7C%20select%20count%281%29&to=1700000900&type=log"
+        );
+        assert_eq!(logstore_resource("app log"), "/logstores/app%20log");
+    }
+
+    // 期望值由独立实现（Python hmac/hashlib）按同一 StringToSign 规则计算
+    #[test]
+    fn sign_v1_known_answer() {
+        let date = "Tue, 14 Nov 2023 22:13:20 GMT";
+        let headers = headers(&[("date", date), ("x-log-signaturemethod", "hmac-sha1")]);
+        let authorization = client(SignatureVersion::V1).sign_v1("/logstores/app-log", &params(), &headers, date);
+        assert_eq!(authorization, "LOG testid:3IcbiJj4THBrLLlfPF/SYckTj0E=");
+    }
+
+    #[test]
+    fn sign_v4_known_answer() {
+        let payload_hash = hex::encode(Sha256::digest(b""));
+        let headers = headers(&[
+            ("x-log-date", "20231114T221320Z"),
+            ("x-log-content-sha256", &payload_hash),
+        ]);
+        let authorization = client(SignatureVersion::V4).sign_v4(
+            "/logstores/app-log",
+            &params(),
+            &headers,
+            "20231114T221320Z",
+            "20231114",
+            &payload_hash,
+        );
+        assert_eq!(
+            authorization,
+            "SLS4-HMAC-SHA256 Credential=testid/20231114/cn-hangzhou/sls/aliyun_v4_request,\
+             Signature=e2cbda49905eea8a2e51fbddd35b9543a19f5111c017ada92f9e3b44c1a9634f"
+        );
+    }
+}
@@ -0,0 +1,208 @@
+// 基于配置的 SLS 查询：校验 project / logstore / 索引字段，解析时间窗口并组装查询语句
+use crate::utils::config::{SlsConfig, SlsLogstore, SlsProject};
+use crate::utils::date_util::{resolve_time_range, resolve_time_zone};
+use anyhow::Result;
+use chrono::Utc;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// 未指定时间窗口时默认查询最近 15 分钟
+pub const DEFAULT_TIME_RANGE: &str = "15m";
+
+/// 按名称查找配置中的 project 与 logstore，找不到时列出可选项
+pub fn resolve_logstore<'a>(
+    config: &'a SlsConfig,
+    project: &str,
+    logstore: &str,
+) -> Result<(&'a SlsProject, &'a SlsLogstore)> {
+    let sls_project = config.projects.iter().find(|p| p.name == project).ok_or_else(|| {
+        let names: Vec<&str> = config.projects.iter().map(|p| p.name.as_str()).collect();
+        anyhow::anyhow!("SLS project '{}' is not configured, available: {}", project, names.join(", "))
+    })?;
+    let sls_logstore = sls_project
+        .logstores
+        .iter()
+        .find(|l| l.name == logstore)
+        .ok_or_else(|| {
+            let names: Vec<&str> = sls_project.logstores.iter().map(|l| l.name.as_str()).collect();
+            anyhow::anyhow!(
+                "Logstore '{}' is not configured in SLS project '{}', available: {}",
+                logstore,
+                project,
+                names.join(", ")
+            )
+        })?;
+    Ok((sls_project, sls_logstore))
+}
+
+/// 计算查询时间窗口（秒级时间戳），time_range 支持 ResolveTimeRange 的全部表达式，如 15m、last 1 hour、from~to
+pub fn resolve_time_window(time_range: Option<&str>) -> Result<(i64, i64)> {
+    let time_range = time_range.map(str::trim).filter(|r| !r.is_empty()).unwrap_or(DEFAULT_TIME_RANGE);
+    let (from, to, _) = resolve_time_range(time_range, resolve_time_zone(None)?, Utc::now())?;
+    Ok((from.timestamp(), to.timestamp()))
+}
+
+/// 组装查询语句：字段过滤条件必须是 logstore 已配置的索引，过滤条件放在分析语句（| 之后）之前
+pub fn build_query(logstore: &SlsLogstore, query: Option<&str>, filters: &BTreeMap<String, String>) -> Result<String> {
+    let unknown: Vec<&str> = filters
+        .keys()
+        .filter(|key| !logstore.indexes.iter().any(|index| &index.name == *key))
+        .map(|key| key.as_str())
+        .collect();
+    if !unknown.is_empty() {
+        let indexes: Vec<&str> = logstore.indexes.iter().map(|index| index.name.as_str()).collect();
+        return Err(anyhow::anyhow!(
+            "Fields [{}] are not indexed in logstore '{}', indexed fields: {}",
+            unknown.join(", "),
+            logstore.name,
+            indexes.join(", ")
+        ));
+    }
+
+    let query = query.map(str::trim).filter(|q| !q.is_empty()).unwrap_or("*");
+    let (search, analytics) = match split_analytics(query) {
+        Some((search, analytics)) => (search.trim(), Some(analytics.trim())),
+        None => (query, None),
+    };
+    let mut conditions: Vec<String> = filters
+        .iter()
+        // 先转义 \ 再转义 "，避免以 \ 结尾的值（如 Windows 路径）闭合不了引号
+        .map(|(key, value)| format!("{}: \"{}\"", key, value.replace('\\', "\\\\").replace('"', "\\\"")))
+        .collect();
+    if !search.is_empty() && search != "*" {
+        conditions.push(format!("({})", search));
+    }
+    let search = if conditions.is_empty() {
+        "*".to_string()
+    } else {
+        conditions.join(" and ")
+    };
+    Ok(match analytics {
+        Some(analytics) => format!("{} | {}", search, analytics),
+        None => search,
+    })
+}
+
+/// 在引号外的第一个 | 处拆分查询语句与分析语句，引号内的 | 属于检索值
+fn split_analytics(query: &str) -> Option<(&str, &str)> {
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for (i, c) in query.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match (c, quote) {
+            ('\\', Some(_)) => escaped = true,
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            ('|', None) => return Some((&query[..i], &query[i + 1..])),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 将日志中内容为 JSON 的字符串字段解析为结构化对象
+pub fn parse_log_record(log: Value) -> Value {
+    match log {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| {
+                    let parsed = match &value {
+                        Value::String(s) if s.trim_start().starts_with(['{', '[']) => {
+                            serde_json::from_str(s).unwrap_or(value)
+                        }
+                        _ => value,
+                    };
+                    (key, parsed)
+                })
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::config::SlsIndex;
+
+    fn logstore() -> SlsLogstore {
+        SlsLogstore {
+            name: "app-log".to_string(),
+            description: String::new(),
+            indexes: ["level", "app"]
+                .into_iter()
+                .map(|name| SlsIndex {
+                    name: name.to_string(),
+                    description: String::new(),
+                    index_type: "text".to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    fn filters(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn defaults_to_all_logs() {
+        assert_eq!(build_query(&logstore(), None, &BTreeMap::new()).unwrap(), "*");
+        assert_eq!(build_query(&logstore(), Some("  "), &BTreeMap::new()).unwrap(), "*");
+    }
+
+    #[test]
+    fn filters_go_before_analytics() {
+        let query = build_query(
+            &logstore(),
+            Some("timeout | select count(1) as c"),
+            &filters(&[("level", "ERROR"), ("app", "order")]),
+        )
+        .unwrap();
+        assert_eq!(query, "app: \"order\" and level: \"ERROR\" and (timeout) | select count(1) as c");
+    }
+
+    #[test]
+    fn filter_values_are_escaped() {
+        let query = build_query(&logstore(), None, &filters(&[("app", "a\"b")])).unwrap();
+        assert_eq!(query, "app: \"a\\\"b\"");
+    }
+
+    #[test]
+    fn filter_values_escape_backslashes() {
+        let query = build_query(&logstore(), Some("* | select count(1)"), &filters(&[("app", "C:\\logs\\")])).unwrap();
+        assert_eq!(query, "app: \"C:\\\\logs\\\\\" | select count(1)");
+        let query = build_query(&logstore(), None, &filters(&[("app", "a\\\"b")])).unwrap();
+        assert_eq!(query, "app: \"a\\\\\\\"b\"");
+    }
+
+    #[test]
+    fn pipe_inside_quotes_is_not_analytics() {
+        let query = build_query(
+            &logstore(),
+            Some("message: \"a|b\" and path: 'x|y' | select count(1)"),
+            &filters(&[("level", "ERROR")]),
+        )
+        .unwrap();
+        assert_eq!(
+            query,
+            "level: \"ERROR\" and (message: \"a|b\" and path: 'x|y') | select count(1)"
+        );
+        let query = build_query(&logstore(), Some("message: \"a\\\"|b\""), &filters(&[("level", "ERROR")])).unwrap();
+        assert_eq!(query, "level: \"ERROR\" and (message: \"a\\\"|b\")");
+    }
+
+    #[test]
+    fn analytics_only_query_keeps_filters() {
+        let query = build_query(&logstore(), Some("* | select count(1)"), &filters(&[("level", "ERROR")])).unwrap();
+        assert_eq!(query, "level: \"ERROR\" | select count(1)");
+    }
+
+    #[test]
+    fn rejects_unindexed_filter_fields() {
+        let error = build_query(&logstore(), None, &filters(&[("trace", "1")])).unwrap_err();
+        assert!(error.to_string().contains("Fields [trace] are not indexed"));
+    }
+}